@@ -0,0 +1,131 @@
+use syn::{DeriveInput, Data, Field, Ident, LitInt, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+/// A single rule from a `#[validate(..)]` field attribute.
+pub enum Rule {
+    /// `non_empty` fails if the field's `is_empty()` returns true.
+    NonEmpty,
+    /// `min_len = N` fails if the field has fewer than N characters.
+    MinLen(LitInt),
+    /// `max_len = N` fails if the field has more than N characters.
+    MaxLen(LitInt),
+    /// `value_object = Path` fails if the `ValueObject` at the path does not validate the field.
+    ValueObject(syn::Path),
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        match name.to_string().as_str() {
+            "non_empty" => Ok(Rule::NonEmpty),
+            "min_len" => {
+                input.parse::<Token![=]>()?;
+                Ok(Rule::MinLen(input.parse()?))
+            },
+            "max_len" => {
+                input.parse::<Token![=]>()?;
+                Ok(Rule::MaxLen(input.parse()?))
+            },
+            "value_object" => {
+                input.parse::<Token![=]>()?;
+                Ok(Rule::ValueObject(input.parse()?))
+            },
+            _ => Err(syn::Error::new(
+                name.span(),
+                "unknown validation rule, expected one of `non_empty`, `min_len`, `max_len` or `value_object`",
+            )),
+        }
+    }
+}
+
+/// `produce_validate` generates the body of `Command::validate` from the `#[validate(..)]` attributes
+/// on the struct's fields.  If no field carries a validate attribute then `None` is returned, and the
+/// default implementation of `validate` from the trait is used.
+pub fn produce_validate(input: &DeriveInput) -> Result<Option<TokenStream2>, syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(st) => &st.fields,
+        _ => return Ok(None),
+    };
+
+    let mut checks = vec![];
+    for field in fields.iter() {
+        for rule in field_rules(field)? {
+            checks.push(implement_check(field, &rule)?);
+        }
+    }
+
+    if checks.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(quote! {
+        fn validate(&self) -> std::result::Result<(), domain_patterns::models::ValidationErrors> {
+            let mut errors = domain_patterns::models::ValidationErrors::new();
+
+            #(#checks)*
+
+            errors.into_result()
+        }
+    }))
+}
+
+/// `field_rules` parses every rule from the `#[validate(..)]` attributes on the field.
+pub fn field_rules(field: &Field) -> Result<Vec<Rule>, syn::Error> {
+    let mut rules = vec![];
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("validate")) {
+        let parsed = attr.parse_args_with(Punctuated::<Rule, Token![,]>::parse_terminated)?;
+        rules.extend(parsed);
+    }
+
+    Ok(rules)
+}
+
+fn implement_check(field: &Field, rule: &Rule) -> Result<TokenStream2, syn::Error> {
+    // Commands are plain parameter objects, so we only support named fields.
+    let field_name = field.ident.as_ref().ok_or_else(|| {
+        let span = field.attrs.iter().find(|a| a.path.is_ident("validate")).map_or(field.ty.span(), |a| a.path.span());
+        syn::Error::new(span, "`#[validate(..)]` is only supported on named fields")
+    })?;
+
+    Ok(implement_rule(rule, &quote! { self.#field_name }, &field_name.to_string()))
+}
+
+/// `implement_rule` generates a check of the rule against the `value` expression, which records a failure
+/// at `path` in a `ValidationErrors` named `errors`.
+pub fn implement_rule(rule: &Rule, value: &TokenStream2, path: &str) -> TokenStream2 {
+    let (failed, code, message) = match rule {
+        Rule::NonEmpty => (
+            quote! { #value.is_empty() },
+            "non_empty".to_string(),
+            quote! { "must not be empty".to_string() },
+        ),
+        Rule::MinLen(len) => (
+            quote! { #value.chars().count() < #len },
+            "min_len".to_string(),
+            quote! { format!("must have a length of at least {}", #len) },
+        ),
+        Rule::MaxLen(len) => (
+            quote! { #value.chars().count() > #len },
+            "max_len".to_string(),
+            quote! { format!("must have a length of at most {}", #len) },
+        ),
+        Rule::ValueObject(vo_path) => {
+            let path_str = quote!(#vo_path).to_string().replace(' ', "");
+            (
+                quote! { <#vo_path as domain_patterns::models::ValueObject<_>>::validate(&#value).is_err() },
+                "value_object".to_string(),
+                quote! { format!("is not a valid {}", #path_str) },
+            )
+        },
+    };
+
+    quote! {
+        if #failed {
+            errors.add(#path, #code, &#message);
+        }
+    }
+}
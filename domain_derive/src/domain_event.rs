@@ -1,100 +1,265 @@
-use syn::{DeriveInput, Data, Field, Path, Error};
+use syn::{DeriveInput, Data, Field, Error, Ident, LitStr, Token};
+use syn::spanned::Spanned;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
 use crate::type_checks::*;
 
 /// `precondition` checks all invariants for the Struct structure that the macro is being applied to.
 /// The following conditions must be true:
-/// 1. There needs to be an `id` field of type `Uuid`.
-/// 2. There needs to be a version field of any integer type (floating point not allowed).
-/// 3. There needs to be an `aggregate_id` field of type `Uuid`.
-/// 4. There needs to be an `occurred` field of type `i64`.
-pub fn precondition(input: &DeriveInput) -> Result<(), syn::Error> {
-    check_id_field(input)?;
-    check_aggregate_id_field(input)?;
-    check_occurred_field(input)?;
-    check_version_field(input)?;
+/// 1. It needs to be a struct with named fields.
+/// 2. There needs to be an id field of a type that implements `Display`, such as `Uuid`.
+/// 3. There needs to be an aggregate id field of a type that implements `Display`, such as `String`, `Uuid` or `Id<T>`.
+/// 4. There needs to be an occurred field of type `i64` (milliseconds), `Timestamp`, `SystemTime` or `DateTime<Utc>`.
+/// 5. There needs to be a version field of any integer type (floating point not allowed).
+///
+/// The fields are named `id`, `aggregate_id`, `occurred` and `version`, unless renamed with `#[event(..)]`.
+pub fn precondition(input: &DeriveInput, fields: &EventFields) -> Result<(), syn::Error> {
+    check_named_struct(input, "DomainEvent")?;
+    // Whether the ids implement Display is left to the compiler, which reports it at the generated `to_string()`.
+    check_field(input, &fields.id, "a type that implements Display, such as Uuid", |_| true)?;
+    check_field(input, &fields.aggregate_id, "a type that implements Display, such as String, Uuid or Id<T>", |_| true)?;
+    check_field(input, &fields.occurred, "type i64, Timestamp, SystemTime or DateTime<Utc>", is_timestamp_type)?;
+    check_field(input, &fields.version, "integer type", is_int_type)?;
 
     Ok(())
 }
 
-fn check_id_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_id_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "expected `id` field with type Uuid"));
+// Checks that the struct has a field with the given name, of a type accepted by `type_check`.  A missing field is
+// reported at the span of the name, which is the name given in `#[event(..)]`, or the struct's name for the default
+// names.  A field of the wrong type is reported at its type.
+fn check_field(input: &DeriveInput, name: &Ident, expected: &str, type_check: fn(&Field) -> bool) -> Result<(), syn::Error> {
+    let message = format!("expected `{}` field with {}", name, expected);
+    match field_named(&input.data, &name.to_string()) {
+        Some(f) if type_check(f) => Ok(()),
+        Some(f) => Err(Error::new(f.ty.span(), message)),
+        None => Err(Error::new(name.span(), message)),
     }
+}
 
-    Ok(())
+/// A single option from an `#[event(..)]` attribute.
+enum EventOption {
+    /// `constructor` generates a `new` constructor that takes an `IdGenerator` and a `Clock`.
+    Constructor,
+    /// `arbitrary` implements proptest's `Arbitrary`, generating events with random field values.
+    Arbitrary,
+    /// `id = "name"` reads the event's id from the named field.
+    Id(Ident),
+    /// `aggregate_id = "name"` reads the aggregate id from the named field.
+    AggregateId(Ident),
+    /// `occurred = "name"` reads the time the event occurred from the named field.
+    Occurred(Ident),
+    /// `version = "name"` reads the version from the named field.
+    Version(Ident),
 }
 
-fn has_id_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "id"
-            })
-        },
-        _ => false,
+impl Parse for EventOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        match name.to_string().as_str() {
+            "constructor" => return Ok(EventOption::Constructor),
+            "arbitrary" => return Ok(EventOption::Arbitrary),
+            _ => (),
+        }
+
+        let unknown = "unknown event option, expected one of `constructor`, `arbitrary`, `id`, `aggregate_id`, `occurred` or `version`";
+        let option: fn(Ident) -> EventOption = match name.to_string().as_str() {
+            "id" => EventOption::Id,
+            "aggregate_id" => EventOption::AggregateId,
+            "occurred" => EventOption::Occurred,
+            "version" => EventOption::Version,
+            _ => return Err(Error::new(name.span(), unknown)),
+        };
+        input.parse::<Token![=]>()?;
+        // The field name is parsed from the string, so it keeps the string's span for error messages.
+        let field: LitStr = input.parse()?;
+        Ok(option(field.parse()?))
     }
 }
 
-fn check_aggregate_id_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_id_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "expected `aggregate_id` field with type Uuid"));
-    }
+/// The options given to `DomainEvent` with `#[event(..)]` attributes.
+#[derive(Default)]
+pub struct Options {
+    constructor: bool,
+    arbitrary: bool,
+    id: Option<Ident>,
+    aggregate_id: Option<Ident>,
+    occurred: Option<Ident>,
+    version: Option<Ident>,
+}
 
-    Ok(())
+/// The names of the fields that hold the event's id, aggregate id, occurred time and version.
+pub struct EventFields {
+    pub id: Ident,
+    pub aggregate_id: Ident,
+    pub occurred: Ident,
+    pub version: Ident,
 }
 
-fn has_aggregate_id_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "aggregate_id"
-                    && is_uuid_type(f)
-            })
-        },
-        _ => false,
+impl Options {
+    /// Returns the names of the event's fields, using the default name for each field that wasn't renamed.  The
+    /// default names carry the span of the struct's name, so a missing field is reported at the struct.
+    pub fn fields(&self, input: &DeriveInput) -> EventFields {
+        let name = |renamed: &Option<Ident>, default: &str| {
+            renamed.clone().unwrap_or_else(|| Ident::new(default, input.ident.span()))
+        };
+
+        EventFields {
+            id: name(&self.id, "id"),
+            aggregate_id: name(&self.aggregate_id, "aggregate_id"),
+            occurred: name(&self.occurred, "occurred"),
+            version: name(&self.version, "version"),
+        }
     }
 }
 
-fn check_occurred_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_id_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "expected `occurred` field with type i64"));
+/// `parse_options` collects the options from every `#[event(..)]` attribute on the struct.
+pub fn parse_options(input: &DeriveInput) -> Result<Options, syn::Error> {
+    let mut options = Options::default();
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident("event")) {
+        let parsed = attr.parse_args_with(Punctuated::<EventOption, Token![,]>::parse_terminated)?;
+        for option in parsed {
+            match option {
+                EventOption::Constructor => options.constructor = true,
+                EventOption::Arbitrary => options.arbitrary = true,
+                EventOption::Id(field) => options.id = Some(field),
+                EventOption::AggregateId(field) => options.aggregate_id = Some(field),
+                EventOption::Occurred(field) => options.occurred = Some(field),
+                EventOption::Version(field) => options.version = Some(field),
+            }
+        }
     }
 
-    Ok(())
+    Ok(options)
 }
 
-fn has_occurred_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "occurred"
-                    && is_timestamp_type(f)
-            })
-        },
-        _ => false,
+/// `produce_constructor` generates a `new` constructor when `#[event(constructor)]` is given.  The constructor
+/// takes an `IdGenerator` for the event's id and a `Clock` for the time it occurred, followed by every other field
+/// in declaration order, so events can be created deterministically in tests.  The constructor returns an
+/// `EventConstructorError` if the generator's id doesn't parse into the id field's type, or the clock's time is
+/// out of range for the occurred field's type.
+pub fn produce_constructor(input: &DeriveInput, options: &Options) -> Result<TokenStream2, syn::Error> {
+    if !options.constructor {
+        return Ok(quote! {});
     }
+
+    let name = &input.ident;
+    let EventFields { id, occurred, .. } = options.fields(input);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields: Vec<&Field> = match &input.data {
+        Data::Struct(st) => st.fields.iter()
+            .filter(|f| f.ident.as_ref().is_some_and(|ident| *ident != id && *ident != occurred))
+            .collect(),
+        _ => return Err(Error::new(input.ident.span(), "expected a struct")),
+    };
+    let idents: Vec<&Ident> = fields.iter().filter_map(|f| f.ident.as_ref()).collect();
+    let types = fields.iter().map(|f| &f.ty);
+    let field_type = |name: &Ident| match field_named(&input.data, &name.to_string()) {
+        Some(field) => Ok(&field.ty),
+        None => Err(Error::new(name.span(), format!("expected `{}` field", name))),
+    };
+    let id_type = field_type(&id)?;
+    let occurred_type = field_type(&occurred)?;
+    let error = quote! { domain_patterns::event::EventConstructorError };
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Creates the event with an id from the id generator, occurring at the clock's current time.  Returns an
+            /// error if the generated id can't be parsed into the id field's type, or the clock's time doesn't fit
+            /// in the occurred field's type.
+            pub fn new<G, C>(id_generator: &G, clock: &C, #(#idents: #types),*)
+                -> std::result::Result<Self, #error<<#id_type as std::str::FromStr>::Err>>
+                where G: domain_patterns::models::IdGenerator + ?Sized,
+                      C: domain_patterns::time::Clock + ?Sized,
+            {
+                Ok(#name {
+                    #id: domain_patterns::models::IdGenerator::next_id(id_generator).parse().map_err(#error::Id)?,
+                    #occurred: <#occurred_type as std::convert::TryFrom<_>>::try_from(
+                        domain_patterns::time::Clock::now(clock)
+                    ).map_err(|e| #error::Occurred(e.into()))?,
+                    #(#idents,)*
+                })
+            }
+        }
+    })
 }
 
-fn check_version_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_version_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "expected `version` field with integer type."));
+/// `produce_arbitrary` implements proptest's `Arbitrary` when `#[event(arbitrary)]` is given.  An id or aggregate id
+/// of type `Uuid`, `String` or `Id<T>` is parsed from random UUIDs, and one of any other type is generated with `any`.
+/// The version is at least 1, the occurred time is converted from a random `Timestamp`, and every other field is
+/// generated with `any`, so its type must implement `Arbitrary`.
+pub fn produce_arbitrary(input: &DeriveInput, options: &Options) -> Result<TokenStream2, syn::Error> {
+    if !options.arbitrary {
+        return Ok(quote! {});
     }
 
-    Ok(())
+    let name = &input.ident;
+    let event_fields = options.fields(input);
+    let proptest = quote! { domain_patterns::strategy::proptest };
+    let fields: Vec<&Field> = match &input.data {
+        Data::Struct(st) => st.fields.iter().collect(),
+        _ => return Err(Error::new(input.ident.span(), "expected a struct")),
+    };
+
+    let mut strategies = vec![];
+    let mut idents = vec![];
+    for field in fields {
+        // Safe to unwrap because the preconditions found the named fields.
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let is_id = *ident == event_fields.id || *ident == event_fields.aggregate_id;
+        let strategy = if is_id && is_uuid_compatible_type(field) {
+            quote! {
+                #proptest::strategy::Strategy::prop_filter_map(
+                    domain_patterns::strategy::ids(),
+                    "generated UUID is not a valid id",
+                    |id| id.parse::<#ty>().ok(),
+                )
+            }
+        } else if *ident == event_fields.version {
+            quote! { (1 as #ty)..=<#ty>::MAX }
+        } else if *ident == event_fields.occurred {
+            quote! {
+                #proptest::strategy::Strategy::prop_map(domain_patterns::strategy::timestamps(), |timestamp| {
+                    <#ty as std::convert::TryFrom<_>>::try_from(timestamp)
+                        .unwrap_or_else(|_| unreachable!("generated timestamps are in range for every occurred type"))
+                })
+            }
+        } else {
+            quote! { #proptest::arbitrary::any::<#ty>() }
+        };
+        strategies.push(strategy);
+        idents.push(ident);
+    }
+
+    // Tuples of strategies are strategies, but only up to twelve elements, so the fields are nested in pairs.
+    let mut strategy = quote! { #proptest::strategy::Just(()) };
+    let mut pattern = quote! { () };
+    for (field_strategy, ident) in strategies.iter().zip(&idents).rev() {
+        strategy = quote! { (#field_strategy, #strategy) };
+        pattern = quote! { (#ident, #pattern) };
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #proptest::arbitrary::Arbitrary for #name #ty_generics #where_clause {
+            type Parameters = ();
+            type Strategy = #proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                let strategy = #proptest::strategy::Strategy::prop_map(#strategy, |#pattern| #name { #(#idents),* });
+                #proptest::strategy::Strategy::boxed(strategy)
+            }
+        }
+    })
 }
 
-fn has_version_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "version"
-                    && is_int_type(f)
-            })
-        },
+// Checks that the field is of a type that ids can be parsed into from a UUID: `Uuid`, `String` or `Id<T>`.
+fn is_uuid_compatible_type(field: &Field) -> bool {
+    match &field.ty {
+        syn::Type::Path(type_path) => type_path.path.segments.iter().last()
+            .is_some_and(|segment| segment.ident == "Uuid" || segment.ident == "String" || segment.ident == "Id"),
         _ => false,
     }
 }
@@ -1,4 +1,5 @@
-use syn::{DeriveInput, Data, Field, Path, Error, Ident};
+use syn::{DeriveInput, Data, Error, Fields, Ident, Type};
+use syn::spanned::Spanned;
 use syn::export::TokenStream2;
 use std::process::abort;
 use proc_macro2::Span;
@@ -8,9 +9,23 @@ use quote::quote;
 /// `precondition` checks all invariants for the Struct structure that the macro is being applied to.
 /// The following conditions must be true:
 /// 1. The data structure the macro is being applied to must be an enum.
+/// 2. Every variant needs to hold a single event, as in `UserCreated(UserCreatedEvent)`.  Whether the event
+///    implements `DomainEvent` is left to the compiler.
 pub fn precondition(input: &DeriveInput) -> Result<(), syn::Error> {
     check_if_enum(input)?;
-    // TODO: Add check that all enum variants implement DomainEvent
+    check_variants(input)?;
+
+    Ok(())
+}
+
+fn check_variants(input: &DeriveInput) -> Result<(), syn::Error> {
+    if let Data::Enum(en) = &input.data {
+        for variant in en.variants.iter() {
+            if !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1) {
+                return Err(Error::new(variant.span(), "expected every variant to hold a single event, such as `UserCreated(UserCreatedEvent)`"));
+            }
+        }
+    }
 
     Ok(())
 }
@@ -54,3 +69,45 @@ pub fn create_inner_match_for_getter(input: &DeriveInput, func_name: String) ->
         }
     };
 }
+
+// Implements `EventVariant` for the inner type of every single field tuple variant, so subscribers can
+// listen to just one variant of the enum.  A type held by more than one variant can't tell which variant it
+// came from, and a type that is one of the enum's own type parameters can't be implemented for outside its
+// crate, so neither gets an impl.
+pub fn create_variant_impls(input: &DeriveInput) -> TokenStream2 {
+    let parent = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let variants = match &input.data {
+        syn::Data::Enum(e) => &e.variants,
+        _ => abort(),
+    };
+
+    let inner_types: Vec<(&Ident, &Type)> = variants.iter()
+        .filter(|v| v.fields.len() == 1)
+        .filter_map(|v| v.fields.iter().next().filter(|f| f.ident.is_none()).map(|f| (&v.ident, &f.ty)))
+        .collect();
+    let type_name = |ty: &Type| quote!(#ty).to_string();
+    let is_type_param = |ty: &Type| input.generics.type_params().any(|param| param.ident == type_name(ty));
+
+    let impls = inner_types.iter()
+        .filter(|(_, ty)| inner_types.iter().filter(|(_, other)| type_name(other) == type_name(ty)).count() == 1)
+        .filter(|(_, ty)| !is_type_param(ty))
+        .map(|(name, ty)| {
+            quote! {
+                impl #impl_generics domain_patterns::event::EventVariant<#parent #ty_generics> for #ty #where_clause {
+                    fn from_event(event: &#parent #ty_generics) -> Option<&Self> {
+                        #[allow(unreachable_patterns)]
+                        match event {
+                            #parent::#name(child) => Some(child),
+                            _ => None,
+                        }
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        #(#impls)*
+    }
+}
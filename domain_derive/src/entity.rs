@@ -1,12 +1,15 @@
-use syn::{DeriveInput, Data, Field, Path, Error, Attribute, DataStruct};
+use syn::{DeriveInput, Data, Field, Error, Attribute, DataStruct};
+use syn::spanned::Spanned;
 use crate::type_checks::*;
 use syn::export::TokenStream2;
 
 /// `precondition` checks all invariants for the Struct structure that the macro is being applied to.
 /// The following conditions must be true:
-/// 1. There needs to be an `id` field of type `Uuid`.
-/// 2. There needs to be a version field of any integer type (floating point not allowed).
+/// 1. It needs to be a struct with named fields.
+/// 2. There needs to be an `id` field of a type that implements `Display`.
+/// 3. None of the fields can be public.
 pub fn precondition(input: &DeriveInput) -> Result<(), syn::Error> {
+    check_named_struct(input, "Entity")?;
     check_id_field(input)?;
     check_public_fields(input)?;
 
@@ -27,8 +30,7 @@ pub fn produce_getters(input: &DeriveInput) -> Result<TokenStream2, syn::Error>
             // (any aggregate root is also an entity) so we should avoid making version getter so
             // we don't have collision with getter from Aggregate root implementation.
             .filter(|f| {
-                let field_name = f.ident.clone().unwrap();
-                field_name != "id" && field_name != "version"
+                f.ident.as_ref().is_some_and(|field_name| field_name != "id" && field_name != "version")
             })
             .map(|f| implement_getter(f))
             .collect::<Vec<_>>();
@@ -55,13 +57,8 @@ fn implement_getter(field: &Field) -> TokenStream2 {
     let docs: Vec<&Attribute> = field
         .attrs
         .iter()
-        .filter_map(|v| {
-            let meta = v.parse_meta().expect("attribute");
-            if meta.path().is_ident("doc") {
-                return Some(v);
-            }
-            None
-        }).collect();
+        .filter(|v| v.path.is_ident("doc"))
+        .collect();
 
         return quote! {
             #(#docs)*
@@ -73,22 +70,17 @@ fn implement_getter(field: &Field) -> TokenStream2 {
 }
 
 fn check_public_fields(input: &DeriveInput) -> Result<(), syn::Error> {
-    if has_public_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "cant have any public fields. Interior mutability should be limited to methods only"));
+    if let Some(field) = public_field(&input.data) {
+        return Err(Error::new(field.span(), "cant have any public fields. Interior mutability should be limited to methods only"));
     }
 
     Ok(())
 }
 
-fn has_public_field(data: &Data) -> bool {
+fn public_field(data: &Data) -> Option<&Field> {
     match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                is_public(f)
-            })
-        },
-        _ => false,
+        Data::Struct(st) => st.fields.iter().find(|f| is_public(f)),
+        _ => None,
     }
 }
 
@@ -100,47 +92,11 @@ fn is_public(field: &Field) -> bool {
 }
 
 fn check_id_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_id_field(&input.data) {
+    // Whether the type implements Display is left to the compiler, which reports it at the generated `to_string()`.
+    if field_named(&input.data, "id").is_none() {
         let input_span = input.ident.span();
         return Err(Error::new(input_span, "expected `id` field with type that implements Display"));
     }
 
     Ok(())
 }
-
-fn check_version_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if !has_version_field(&input.data) {
-        let input_span = input.ident.span();
-        return Err(Error::new(input_span, "expected `version` field with integer type."));
-    }
-
-    Ok(())
-}
-
-fn has_id_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "id"
-                    // TODO: Add check that type implements Display.
-            })
-        },
-        _ => false,
-    }
-}
-
-fn has_version_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "version"
-                    && is_int_type(f)
-            })
-        },
-        _ => false,
-    }
-}
-
-
-
-
@@ -24,60 +24,74 @@
 //! The `ValueSetup` derive macro can be used to setup as much boilerplate as possible
 //! for your choosen value object.  It checks some preconditions:
 //!
-//! 1. You are applying this to a struct.
-//! 2. Your struct has a single field called `value` of any type that is clonable.
+//! 1. You are applying this to a struct, or to a C-like enum whose variants carry `#[value = ".."]` attributes.
+//! 2. Your struct is a tuple struct with a single field, or has a single field called `value`, of any type
+//!    that is clonable.  It can also have several named fields for a composite value object.
 //!
 //! Once you've used this macro, you will still need to implement the `ValueObject` trait,
-//! but you will not have to implement `TryFrom` (or create the validation error for `TryFrom`, this
-//! is handled by the macro), or implement `PartialEq` or `Clone`.
-//!
-//! In case you need to use the validation error elsewhere, the created validation error will be the
-//! name of your struct with ValidationError appended.  For example, if you have an `Email` struct,
-//! then the generated validation error will be called `EmailValidationError`.
+//! but you will not have to implement `TryFrom`, `Display`, `PartialEq` or `Clone`.  The error type of
+//! the generated `TryFrom` can be set with `#[value_object(error = path)]`, and otherwise a `<Name>ValidationError`
+//! is generated.  The macro also implements `FieldValidation`, so failures can be collected into `ValidationErrors`.
 //!
 //! ```edition2018
 //! #[macro_use]
 //! extern crate domain_derive;
 //!
-//! use domain_patterns::ValueObject;
+//! use domain_patterns::models::ValueObject;
 //! use regex::Regex;
 //!
+//! #[derive(Debug)]
+//! pub struct Error;
+//!
 //! #[derive(ValueSetup)]
+//! #[value_object(error = Error)]
 //! pub struct Email {
 //!     pub value: String,
 //! }
 //!
 //! impl ValueObject<String> for Email {
-//!     fn validate(value: &String) -> bool {
+//!     type ValueError = Error;
+//!
+//!     fn validate(value: &String) -> Result<(), Error> {
 //!         let email_rx = Regex::new(
 //!             r"^(?i)[a-z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?(?:.[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?)*$"
 //!         ).unwrap();
 //!
-//!         email_rx.is_match(value)
+//!         if !email_rx.is_match(value) {
+//!             return Err(Error);
+//!         }
+//!
+//!         Ok(())
 //!     }
 //!
-//!     fn value(&self) -> &String {
-//!         return &self.value;
+//!     fn value(&self) -> String {
+//!         self.value.clone()
 //!     }
 //! }
+//! # fn main() {}
 //! ```
 //!
 //! # DomainEvent macro
 //! The `DomainEvent` macro should be applied to a struct that represents a DomainEvent. It completely
 //! implements all methods of the `DomainEvent` trait, as long as some preconditions are met:
 //!
-//! 1. You are applying this to a struct.
-//! 2. There needs to be an `id` field of type `Uuid`.
+//! 1. You are applying this to a struct with named fields.
+//! 2. There needs to be an `id` field of a type which can have `to_string()` called on it, such as `Uuid`.
 //! 3. There needs to be a version field of any integer type (floating point not allowed).
-//! 4. There needs to be an `aggregate_id` field of type `Uuid`.
-//! 5. There needs to be an `occurred` field of type `i64`.
+//! 4. There needs to be an `aggregate_id` field of a type which can have `to_string()` called on it, such as `Uuid`.
+//! 5. There needs to be an `occurred` field of type `i64` (milliseconds since the unix epoch), `Timestamp`,
+//!    `SystemTime` or chrono's `DateTime<Utc>`.
+//!
+//! The fields can have other names if they're given with `#[event(..)]`, as in `#[event(occurred = "at")]`.
 //!
 //! ```edition2018
 //! #[macro_use]
 //! extern crate domain_derive;
 //!
 //! use uuid::Uuid;
+//! use serde::Serialize;
 //! use domain_patterns::event::DomainEvent;
+//! use domain_patterns::message::Message;
 //!
 //! #[derive(Serialize, Clone, DomainEvent)]
 //! pub struct FirstNameUpdatedEvent {
@@ -87,19 +101,22 @@
 //!     pub id: Uuid,
 //!     pub occurred: i64,
 //! }
+//! # fn main() {}
 //! ```
 //!
 //! # DomainEvents macro
 //! The `DomainEvents` macro should be applied to an enum that holds variants which are all Domain Events.
-//! This is a very thin wrapper, and all the macro does is check that the structure is an enum, and then applies
-//! the trait, which has no methods.
+//! Every variant holds a single event, and the macro implements `DomainEvent` for the enum by calling the inner
+//! event's methods.
 //!
 //! ```edition2018
 //! #[macro_use]
 //! extern crate domain_derive;
 //!
 //! use uuid::Uuid;
-//! use domain_patterns::event::{DomainEvent, DomainEvents};
+//! use serde::Serialize;
+//! use domain_patterns::event::DomainEvent;
+//! use domain_patterns::message::Message;
 //!
 //! #[derive(Serialize, Clone, DomainEvent)]
 //! pub struct FirstNameUpdatedEvent {
@@ -114,6 +131,7 @@
 //! pub enum UserEvents {
 //!     FirstNameUpdated(FirstNameUpdatedEvent),
 //! }
+//! # fn main() {}
 //! ```
 
 #![recursion_limit = "128"]
@@ -129,18 +147,21 @@ mod value_object;
 mod domain_event;
 mod domain_events;
 mod type_checks;
+mod command;
 
 use crate::proc_macro::TokenStream;
 use syn::DeriveInput;
-use syn::spanned::Spanned;
-use crate::domain_events::create_inner_match_for_getter;
+use crate::domain_events::{create_inner_match_for_getter, create_variant_impls};
 
 /// The `Entity` derive macro can be used to automatically implement all methods of the `Entity` trait
 /// from the `domain_patterns` crate.  This only works if certain preconditions are met:
 ///
-/// 1. You are applying this to a struct.
+/// 1. You are applying this to a struct with named fields.
 /// 2. Your struct has an `id` field of a type which can have `to_string()` called on it.
-/// 3. Your struct has a `version` field which is some integer type.
+/// 3. None of your fields are public, as changes to an entity should go through its methods.  The macro generates
+///    a getter for every field other than `id` and `version` instead.
+///
+/// If a precondition isn't met, the macro reports a compile error pointing at the struct or the offending field.
 ///
 /// ```edition2018
 /// #[macro_use]
@@ -157,7 +178,9 @@ use crate::domain_events::create_inner_match_for_getter;
 pub fn entity_derive(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input as DeriveInput);
 
-    entity::precondition(&input).expect("Entity procedural macro failed preconditions");
+    if let Err(e) = entity::precondition(&input) {
+        return TokenStream::from(e.to_compile_error());
+    }
 
     // Struct name
     let name = &input.ident;
@@ -177,7 +200,10 @@ pub fn entity_derive(input: TokenStream) -> TokenStream {
         }
     });
 
-    let getters = entity::produce_getters(&input).expect("Entity macro failed to produce getters");
+    let getters = match entity::produce_getters(&input) {
+        Ok(getters) => getters,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
     streams.push(getters);
 
     let expanded = quote! {
@@ -190,31 +216,82 @@ pub fn entity_derive(input: TokenStream) -> TokenStream {
 /// The `ValueSetup` derive macro can be used to setup as much boilerplate as possible
 /// for your choosen value object.  It checks some preconditions:
 ///
-/// 1. You are applying this to a struct.
-/// 2. Your struct has a single field called `value` of any type that is clonable.
+/// 1. You are applying this to a struct, or to an enum of unit variants (see enum value objects below).
+/// 2. Your struct is a tuple struct with a single field, or has a single field called `value`, or several named
+///    fields (see composite value objects below).  Fields can be of any clonable type, including generic and
+///    fully qualified types such as `Option<String>`, but not a bare type parameter of the struct.
+///
+/// A single value is displayed with its own `Display` implementation, except for an `Option`, which is displayed
+/// as its inner value or as an empty string.
 ///
 /// Once you've used this macro, you will still need to implement the `ValueObject` trait,
-/// but you will not have to implement `TryFrom` (or create the validation error for `TryFrom`, this
-/// is handled by the macro), or implement `PartialEq` or `Clone`.
+/// but you will not have to implement `TryFrom`, `Display`, `PartialEq` or `Clone`.
+///
+/// The error type of the generated `TryFrom` can be chosen with `#[value_object(error = path)]`, in which case
+/// your `ValueError` must convert into it.  Otherwise the macro generates an error named after your struct with
+/// ValidationError appended, holding the `ValidationErrors`.  For example, if you have an `Email` struct, then
+/// the generated validation error will be called `EmailValidationError`.  This requires your `ValueError` to
+/// implement `Display`, which becomes the message of the validation error.
+///
+/// The macro also implements `FieldValidation`, so the value object can be validated alongside others with
+/// `ValidateAll`, reporting every failure as `ValidationErrors`.  The error code defaults to `invalid_`
+/// followed by the snake cased struct name, and can be set with `#[value_object(code = "..")]`.
+///
+/// Common validators can be given as options instead of writing `validate` by hand, in which case the
+/// macro generates the whole `ValueObject` impl, with `ValidationErrors` as the `ValueError`.  Every validator
+/// is checked and every failure is reported, with one of the codes `min_len`, `max_len`, `range` or `regex`:
+///
+/// 1. `min_len = N` and `max_len = N` - the value must have at least or at most `N` characters.
+/// 2. `range = 0..=100` - the range must contain the value.  Any range expression works.
+/// 3. `regex = "..."` - the value must match the regular expression, which is checked when the macro runs, so an
+///    invalid pattern is a compile error.  It needs the `regex` feature of `domain_patterns`.
+/// 4. `trim` and `lowercase` - the value is normalized before it is validated, and stored normalized.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use std::convert::TryFrom;
+/// use domain_patterns::models::ValueObject;
+///
+/// #[derive(ValueSetup)]
+/// #[value_object(trim, lowercase, min_len = 3, max_len = 254, regex = r"^[^@\s]+@[^@\s]+$")]
+/// pub struct Email(String);
+///
+/// fn main() {
+///     let email = Email::try_from(" Someone@Example.com ".to_string()).unwrap();
+///     assert_eq!(email.value(), "someone@example.com");
 ///
-/// In case you need to use the validation error elsewhere, the created validation error will be the
-/// name of your struct with ValidationError appended.  For example, if you have an `Email` struct,
-/// then the generated validation error will be called `EmailValidationError`.
+///     let error: EmailValidationError = Email::try_from("no".to_string()).err().unwrap();
+///     assert_eq!(error.errors.errors[0].code, "min_len");
+/// }
+/// ```
 ///
 /// ```edition2018
 /// #[macro_use]
 /// extern crate domain_derive;
 ///
-/// use domain_patterns::ValueObject;
+/// use std::fmt;
+/// use domain_patterns::models::{ValueObject, FieldValidation};
 /// use regex::Regex;
 ///
+/// // This would be the root error for the crate.
+/// #[derive(Debug)]
+/// pub struct Error;
+///
+/// impl fmt::Display for Error {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "is not a valid email address")
+///     }
+/// }
+///
 /// #[derive(ValueSetup)]
+/// #[value_object(code = "bad_email")]
 /// pub struct Email {
 ///     pub value: String,
 /// }
 ///
 /// impl ValueObject<String> for Email {
-///     // This error would be the root error for the crate, using failure most likely.
 ///     type ValueError = Error;
 ///
 ///     fn validate(value: &String) -> Result<(), Self::ValueError> {
@@ -223,85 +300,215 @@ pub fn entity_derive(input: TokenStream) -> TokenStream {
 ///         ).unwrap();
 ///
 ///         if !email_rx.is_match(value) {
-///             return Err(EmailValidationError)
+///             return Err(Error)
 ///         }
 ///
 ///         Ok(())
 ///     }
 ///
-///     fn value(&self) -> &String {
-///         return &self.value;
+///     fn value(&self) -> String {
+///         self.value.clone()
 ///     }
 /// }
+///
+/// fn main() {
+///     let errors = Email::validate_field("email", &"not an email".to_string()).unwrap_err();
+///     assert_eq!(errors.errors[0].code, "bad_email");
+/// }
 /// ```
-#[proc_macro_derive(ValueSetup)]
+///
+/// # Composite value objects
+///
+/// Value objects such as an address or a date range hold several fields, and have rules that span them.  When
+/// applied to a struct with several named fields, the macro implements `Display`, `PartialEq` and `Clone`
+/// field-wise, and the whole `ValueObject` trait for the tuple of the field types.  It also generates a
+/// validating `new` constructor that takes every field in order.
+///
+/// Fields can carry the same `#[validate(..)]` rules as commands, and failures are reported at the field's name.
+/// Once every field is valid, each `#[value_object(check = path)]` function is called with the value object to
+/// check rules that span fields.  `Display` writes the fields separated by commas, unless a format string using
+/// the field names is given with `#[value_object(display = "..")]`.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use domain_patterns::models::ValidationErrors;
+///
+/// #[derive(ValueSetup)]
+/// #[value_object(check = starts_before_end, display = "{start} to {end}")]
+/// pub struct DateRange {
+///     #[validate(non_empty)]
+///     pub start: String,
+///     #[validate(non_empty)]
+///     pub end: String,
+/// }
+///
+/// fn starts_before_end(range: &DateRange) -> Result<(), ValidationErrors> {
+///     if range.start > range.end {
+///         return Err(ValidationErrors::single("", "starts_after_end", "must start before it ends"));
+///     }
+///
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let range = DateRange::new("2019-08-01".to_string(), "2019-08-31".to_string()).unwrap();
+///     assert_eq!(range.to_string(), "2019-08-01 to 2019-08-31");
+///
+///     assert!(DateRange::new("2019-09-01".to_string(), "2019-08-31".to_string()).is_err());
+/// }
+/// ```
+///
+/// # Enum value objects
+///
+/// A C-like enum, where every variant carries a `#[value = ".."]` attribute, becomes a `ValueObject<String>`.  The
+/// macro implements `TryFrom<String>`, `FromStr` and `Display` from the variants' values, along with `PartialEq`
+/// and `Clone`.  Strings that aren't one of the values fail validation with the code `one_of`.  An
+/// `all_values()` function returns every value in declaration order, which is handy for populating dropdowns.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use domain_patterns::models::ValueObject;
+///
+/// #[derive(ValueSetup, Debug)]
+/// pub enum Status {
+///     #[value = "active"]
+///     Active,
+///     #[value = "closed"]
+///     Closed,
+/// }
+///
+/// fn main() {
+///     let status: Status = "closed".parse().unwrap();
+///     assert_eq!(status, Status::Closed);
+///     assert_eq!(status.value(), "closed".to_string());
+///     assert_eq!(Status::all_values(), &["active", "closed"]);
+///
+///     assert!("deleted".parse::<Status>().is_err());
+/// }
+/// ```
+///
+/// # Serde
+///
+/// With `#[value_object(serde)]` the macro also implements `Serialize` and `Deserialize` for value objects holding
+/// a single value, and for enums.  The value object is serialized as its value, and deserializing goes through
+/// `TryFrom`, so structs holding value objects can't be deserialized with invalid values.  The error message of
+/// the value object becomes the deserialization error.  Your crate needs to depend on `serde`.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(ValueSetup)]
+/// #[value_object(trim, min_len = 1, serde)]
+/// pub struct Name(String);
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Person {
+///     name: Name,
+/// }
+///
+/// fn main() {
+///     let person: Person = serde_json::from_str(r#"{"name":" Alice "}"#).unwrap();
+///     assert_eq!(serde_json::to_string(&person).unwrap(), r#"{"name":"Alice"}"#);
+///
+///     let error = serde_json::from_str::<Person>(r#"{"name":"  "}"#).err().unwrap();
+///     assert!(error.to_string().contains("must have a length of at least 1"));
+/// }
+/// ```
+///
+/// # Property testing
+///
+/// With the `proptest` feature of `domain_patterns` enabled, `#[value_object(arbitrary)]` implements proptest's
+/// `Arbitrary`, so `any::<T>()` generates value objects that pass validation.  Inputs are generated from the
+/// `regex`, `range`, `min_len` and `max_len` validators, or from `any` of the value's type, and passed through
+/// `TryFrom`.  For values whose validation the macro can't see, such as a hand written `validate`, give a function
+/// returning a strategy for valid inputs with `#[value_object(arbitrary = path)]`.  The value object must implement
+/// `Debug`.
+///
+/// Value objects holding a single value, and enums, also implement `ValueStrategy`, whose `invalid()` generates
+/// inputs that fail validation, for negative tests.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use domain_patterns::models::ValueObject;
+/// use domain_patterns::strategy::ValueStrategy;
+/// use domain_patterns::strategy::proptest::prelude::*;
+/// use domain_patterns::strategy::proptest::test_runner::TestRunner;
+///
+/// #[derive(ValueSetup, Debug)]
+/// #[value_object(regex = r"^[A-Z]{3}$", arbitrary)]
+/// pub struct CurrencyCode(String);
+///
+/// fn main() {
+///     let mut runner = TestRunner::default();
+///
+///     runner.run(&any::<CurrencyCode>(), |code| {
+///         prop_assert_eq!(code.value().len(), 3);
+///         Ok(())
+///     }).unwrap();
+///
+///     runner.run(&CurrencyCode::invalid(), |input| {
+///         prop_assert!(CurrencyCode::validate(&input).is_err());
+///         Ok(())
+///     }).unwrap();
+/// }
+/// ```
+#[proc_macro_derive(ValueSetup, attributes(value_object, validate, value))]
 pub fn value_object_derive(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input as DeriveInput);
 
-    // Struct name
-    let name = &input.ident;
-    let name_str = name.to_string();
-
-    value_object::precondition(&input).expect("ValueSetup macro failed preconditions");
-
-    // safe to unwrap because we check for existence of value field in precondition.
-    let type_name = &value_object::value_type_name(&input.data).unwrap();
-
-    // TODO: Likely remove this, as we use a parent enum now which is predicatably named.
-    // let error_struct_name = &value_object::error_name_from_type(name, input.span());
-
-    let expanded = quote! {
-        impl std::fmt::Display for #name {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                write!(f, "{}", self.value)
-            }
-        }
+    if let Err(e) = value_object::precondition(&input) {
+        return TokenStream::from(e.to_compile_error());
+    }
 
-        impl std::cmp::PartialEq for #name {
-            fn eq(&self, other: &Self) -> bool {
-                self.value == other.value
-            }
-        }
-
-        impl std::clone::Clone for #name {
-            fn clone(&self) -> Self {
-                #name {
-                    value: self.value.clone()
-                }
-            }
-        }
-
-        impl std::convert::TryFrom<#type_name> for #name {
-            type Error = crate::Error;
-
-            fn try_from(value: #type_name) -> std::result::Result<Self, Self::Error> {
-                Self::validate(&value)?;
+    let options = match value_object::parse_options(&input) {
+        Ok(options) => options,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
 
-                Ok(#name {
-                    value,
-                })
-            }
-        }
+    let expanded = if value_object::is_enum(&input) {
+        value_object::produce_enum(&input, &options)
+    } else if value_object::is_composite(&input) {
+        value_object::produce_composite(&input, &options)
+    } else {
+        value_object::produce_newtype(&input, &options)
     };
 
-    TokenStream::from(expanded)
+    match expanded {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
 }
 
 /// The `DomainEvent` macro should be applied to a struct that represents a DomainEvent. It completely
 /// implements all methods of the `DomainEvent` trait, as long as some preconditions are met:
 ///
-/// 1. You are applying this to a struct.
-/// 2. There needs to be an `id` field of type `Uuid`.
+/// 1. You are applying this to a struct with named fields.
+/// 2. There needs to be an `id` field of a type which can have `to_string()` called on it, such as `Uuid` or `Id<T>`.
 /// 3. There needs to be a version field of any integer type (floating point not allowed).
-/// 4. There needs to be an `aggregate_id` field of type `Uuid`.
-/// 5. There needs to be an `occurred` field of type `i64`.
+/// 4. There needs to be an `aggregate_id` field of a type which can have `to_string()` called on it, such as
+///    `String`, `Uuid` or `Id<T>`.
+/// 5. There needs to be an `occurred` field of type `i64` (milliseconds since the unix epoch), `Timestamp`,
+///    `SystemTime` or chrono's `DateTime<Utc>`.  `occurred()` converts it to a `Timestamp`.
+///
+/// Unmet preconditions are reported as compile errors at the struct, or at the type of a field with the wrong type.
 ///
 /// ```edition2018
 /// #[macro_use]
 /// extern crate domain_derive;
 ///
 /// use uuid::Uuid;
+/// use serde::Serialize;
 /// use domain_patterns::event::DomainEvent;
+/// use domain_patterns::message::Message;
 ///
 /// #[derive(Serialize, Clone, DomainEvent)]
 /// pub struct FirstNameUpdatedEvent {
@@ -311,51 +518,150 @@ pub fn value_object_derive(input: TokenStream) -> TokenStream {
 ///     pub version: u64,
 ///     pub occurred: i64,
 /// }
+/// # fn main() {}
+/// ```
+///
+/// Events whose fields have other names can give them with `#[event(..)]`, using the `id`, `aggregate_id`,
+/// `occurred` and `version` options.  A renamed field that doesn't exist is reported at its name in the attribute.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use uuid::Uuid;
+/// use domain_patterns::event::DomainEvent;
+/// use domain_patterns::message::Message;
+///
+/// #[derive(Clone, DomainEvent)]
+/// #[event(id = "event_id", aggregate_id = "user_id", occurred = "at")]
+/// pub struct UserRegisteredEvent {
+///     pub event_id: Uuid,
+///     pub user_id: Uuid,
+///     pub version: u64,
+///     pub at: i64,
+/// }
+///
+/// fn main() {
+///     let user_id = Uuid::new_v4();
+///     let event = UserRegisteredEvent { event_id: Uuid::new_v4(), user_id, version: 1, at: 1_000 };
+///     assert_eq!(event.aggregate_id(), user_id.to_string());
+///     assert_eq!(event.occurred().as_millis(), 1_000);
+/// }
+/// ```
+///
+/// With `#[event(constructor)]` the macro also generates a `new` constructor that takes an `IdGenerator` and a
+/// `Clock` from `domain_patterns`, followed by every field other than the id and occurred fields in declaration
+/// order.  The id is parsed from the generator's next id, and the occurred field is the clock's current time, so
+/// tests can pass a `SequentialIdGenerator` and a `FixedClock` to create predictable events.  `new` returns an
+/// `EventConstructorError` when the generator's id doesn't parse, such as a `FixedIdGenerator` with `"order-1"` for
+/// a `Uuid` id, or when the clock's time is out of range for the occurred field's type.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use uuid::Uuid;
+/// use domain_patterns::event::DomainEvent;
+/// use domain_patterns::message::Message;
+/// use domain_patterns::models::SequentialIdGenerator;
+/// use domain_patterns::time::{FixedClock, Timestamp};
+///
+/// #[derive(Clone, DomainEvent)]
+/// #[event(constructor)]
+/// pub struct FirstNameUpdatedEvent {
+///     pub id: Uuid,
+///     pub aggregate_id: String,
+///     pub first_name: String,
+///     pub version: u64,
+///     pub occurred: i64,
+/// }
+///
+/// fn main() {
+///     let ids = SequentialIdGenerator::new();
+///     let clock = FixedClock::new(Timestamp::from_millis(1_000));
+///
+///     let event = FirstNameUpdatedEvent::new(&ids, &clock, "user-1".to_string(), "Alice".to_string(), 1).unwrap();
+///     assert_eq!(event.id(), "00000000-0000-0000-0000-000000000001");
+///     assert_eq!(event.occurred().as_millis(), 1_000);
+/// }
 /// ```
-#[proc_macro_derive(DomainEvent)]
+///
+/// With the `proptest` feature of `domain_patterns` enabled, `#[event(arbitrary)]` implements proptest's `Arbitrary`
+/// for the event.  The `id` and `aggregate_id` are random UUIDs when they're of type `Uuid`, `String` or `Id<T>`, and
+/// are generated with `any` otherwise.  The `version` is at least 1, `occurred` is a random time, and every other field
+/// is generated with `any`, so its type must implement `Arbitrary`, as value objects do with `#[value_object(arbitrary)]`.  The event must implement `Debug`.
+#[proc_macro_derive(DomainEvent, attributes(event))]
 pub fn domain_event_derive(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input as DeriveInput);
 
     // Struct name
     let name = &input.ident;
 
-    domain_event::precondition(&input).expect("DomainEvent macro failed preconditions");
+    let options = match domain_event::parse_options(&input) {
+        Ok(options) => options,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    let fields = options.fields(&input);
+
+    if let Err(e) = domain_event::precondition(&input, &fields) {
+        return TokenStream::from(e.to_compile_error());
+    }
+    let constructor = match domain_event::produce_constructor(&input, &options) {
+        Ok(constructor) => constructor,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    let arbitrary = match domain_event::produce_arbitrary(&input, &options) {
+        Ok(arbitrary) => arbitrary,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
 
+    let domain_event::EventFields { id, aggregate_id, occurred, version } = fields;
     let expanded = quote! {
         impl DomainEvent for #name {
-            fn occurred(&self) -> i64 {
-                self.occurred
+            fn occurred(&self) -> domain_patterns::time::Timestamp {
+                domain_patterns::time::Timestamp::from(self.#occurred)
             }
 
             fn id(&self) -> String {
-                self.id.to_string()
+                self.#id.to_string()
             }
 
             fn aggregate_id(&self) -> String {
-                self.aggregate_id.clone()
+                self.#aggregate_id.to_string()
             }
 
             fn version(&self) -> u64 {
-                self.version as u64
+                self.#version as u64
             }
         }
 
         impl Message for #name {}
+
+        #constructor
+
+        #arbitrary
     };
 
     TokenStream::from(expanded)
 }
 
 /// The `DomainEvents` macro should be applied to an enum that holds variants which are all Domain Events.
-/// This is a very thin wrapper, and all the macro does is check that the structure is an enum, and then applies
-/// the trait, which has no methods.
+/// Every variant needs to hold a single event, as in `FirstNameUpdated(FirstNameUpdatedEvent)`, and the macro
+/// implements `DomainEvent` for the enum by calling the inner event's methods.  If the enum doesn't have this
+/// shape, the macro reports a compile error pointing at the offending variant.
+///
+/// It also implements `EventVariant` for each variant's inner event, which is what allows subscribing to a
+/// single variant on the `EventBus`.  An event type held by more than one variant, or a variant that holds one of
+/// the enum's type parameters, is skipped, so those variants can only be handled through the whole enum.
 ///
 /// ```edition2018
 /// #[macro_use]
 /// extern crate domain_derive;
 ///
 /// use uuid::Uuid;
-/// use domain_patterns::event::{DomainEvent, DomainEvents};
+/// use serde::Serialize;
+/// use domain_patterns::event::DomainEvent;
+/// use domain_patterns::message::Message;
 ///
 /// #[derive(Serialize, Clone, DomainEvent)]
 /// pub struct FirstNameUpdatedEvent {
@@ -370,6 +676,7 @@ pub fn domain_event_derive(input: TokenStream) -> TokenStream {
 /// pub enum UserEvents {
 ///     FirstNameUpdated(FirstNameUpdatedEvent),
 /// }
+/// # fn main() {}
 /// ```
 #[proc_macro_derive(DomainEvents)]
 pub fn domain_events_derive(input: TokenStream) -> TokenStream {
@@ -378,7 +685,9 @@ pub fn domain_events_derive(input: TokenStream) -> TokenStream {
     // Struct name
     let name = &input.ident;
 
-    domain_events::precondition(&input).expect("DomainEvents macro failed preconditions");
+    if let Err(e) = domain_events::precondition(&input) {
+        return TokenStream::from(e.to_compile_error());
+    }
 
     // Get match statements for each function, so we automatically call function of inner variant.
     let occurred_match = create_inner_match_for_getter(&input, "occurred".to_string());
@@ -386,9 +695,13 @@ pub fn domain_events_derive(input: TokenStream) -> TokenStream {
     let aggregate_id_match = create_inner_match_for_getter(&input, "aggregate_id".to_string());
     let version_match = create_inner_match_for_getter(&input, "version".to_string());
 
+    // Lets subscribers on the event bus pick out a single variant.
+    let variant_impls = create_variant_impls(&input);
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     let expanded = quote! {
-        impl DomainEvent for #name {
-            fn occurred(&self) -> i64 {
+        impl #impl_generics DomainEvent for #name #ty_generics #where_clause {
+            fn occurred(&self) -> domain_patterns::time::Timestamp {
                 #occurred_match
             }
 
@@ -405,7 +718,9 @@ pub fn domain_events_derive(input: TokenStream) -> TokenStream {
             }
         }
 
-        impl Message for #name {}
+        impl #impl_generics Message for #name #ty_generics #where_clause {}
+
+        #variant_impls
     };
 
     TokenStream::from(expanded)
@@ -413,17 +728,51 @@ pub fn domain_events_derive(input: TokenStream) -> TokenStream {
 
 /// The `Command` derive macro can be used to automatically implement the Command and Message marker traits
 /// from the `domain_patterns` crate.
-#[proc_macro_derive(Command)]
+///
+/// Fields can optionally carry `#[validate(..)]` attributes, in which case the macro also generates the
+/// `validate` method of the `Command` trait.  Every rule on every field is checked, and all failures are
+/// collected into a single `ValidationErrors`, with the field name as the path of each error.  The supported rules are:
+///
+/// 1. `non_empty` - the field's `is_empty()` must return false.
+/// 2. `min_len = N` - the field must have at least `N` characters.
+/// 3. `max_len = N` - the field must have at most `N` characters.
+/// 4. `value_object = Path` - the value object at `Path` must successfully `validate` the field.
+///
+/// ```edition2018
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use domain_patterns::command::Command;
+/// use domain_patterns::message::Message;
+///
+/// #[derive(Command)]
+/// pub struct ChangeNameCommand {
+///     pub id: String,
+///     #[validate(non_empty, max_len = 255)]
+///     pub first_name: String,
+/// }
+///
+/// # fn main() {
+/// let command = ChangeNameCommand { id: "1".to_string(), first_name: "".to_string() };
+/// assert!(command.validate().is_err());
+/// # }
+/// ```
+#[proc_macro_derive(Command, attributes(validate))]
 pub fn command_derive(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input as DeriveInput);
 
-    // No precondition checks that I can think of since these are just simple marker traits
-
     // Struct name
     let name = &input.ident;
 
+    let validate = match command::produce_validate(&input) {
+        Ok(validate) => validate,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+
     let expanded = quote! {
-        impl Command for #name {}
+        impl Command for #name {
+            #validate
+        }
         impl Message for #name {}
     };
 
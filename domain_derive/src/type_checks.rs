@@ -1,16 +1,4 @@
-use syn::{DeriveInput, Data, Field, Path, Error};
-
-pub(crate) fn is_uuid_type(field: &Field) -> bool {
-    fn path_is_uuid(path: &Path) -> bool {
-        path.segments.iter().next().unwrap().ident.to_string().to_lowercase().contains("uuid")
-    }
-    match &field.ty {
-        syn::Type::Path(type_path) if path_is_uuid(&type_path.path) => {
-            true
-        },
-        _ => false,
-    }
-}
+use syn::{DeriveInput, Data, Field, GenericArgument, Path, PathArguments, Type, Error};
 
 pub(crate) fn is_int_type(field: &Field) -> bool {
     fn path_is_int(path: &Path) -> bool {
@@ -34,15 +22,48 @@ pub(crate) fn is_int_type(field: &Field) -> bool {
     }
 }
 
+/// `is_timestamp_type` returns true for the types `Timestamp` can be created from: `i64` milliseconds, `Timestamp`
+/// itself, `SystemTime` and chrono's `DateTime<Utc>`.  A `DateTime` in any other time zone is rejected.
 pub(crate) fn is_timestamp_type(field: &Field) -> bool {
-    fn path_is_i64(path: &Path) -> bool {
-        let path_str = path.segments.iter().next().unwrap().ident.to_string();
-        &path_str == "u64"
+    fn path_is_timestamp(path: &Path) -> bool {
+        match path.segments.iter().last() {
+            Some(segment) => {
+                let path_str = segment.ident.to_string();
+                &path_str == "i64"
+                    || &path_str == "Timestamp"
+                    || &path_str == "SystemTime"
+                    || (&path_str == "DateTime" && is_utc_argument(&segment.arguments))
+            },
+            None => false,
+        }
     }
-    match &field.ty {
-        syn::Type::Path(type_path) if path_is_i64(&type_path.path) => {
-            true
-        },
-        _ => false,
+    fn is_utc_argument(arguments: &PathArguments) -> bool {
+        match arguments {
+            PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+                Some(GenericArgument::Type(Type::Path(type_path))) => {
+                    type_path.path.segments.iter().last().is_some_and(|segment| segment.ident == "Utc")
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+    matches!(&field.ty, Type::Path(type_path) if path_is_timestamp(&type_path.path))
+}
+
+/// `check_named_struct` returns an error at the type's name unless it is a struct with named fields, which the
+/// derive named `derive` requires.
+pub(crate) fn check_named_struct(input: &DeriveInput, derive: &str) -> Result<(), Error> {
+    match &input.data {
+        Data::Struct(st) if matches!(st.fields, syn::Fields::Named(_)) => Ok(()),
+        _ => Err(Error::new(input.ident.span(), format!("{} can only be derived for a struct with named fields", derive))),
+    }
+}
+
+/// `field_named` returns the field with the given name, if the type is a struct that has one.
+pub(crate) fn field_named<'a>(data: &'a Data, name: &str) -> Option<&'a Field> {
+    match data {
+        Data::Struct(st) => st.fields.iter().find(|f| f.ident.as_ref().is_some_and(|ident| ident == name)),
+        _ => None,
     }
 }
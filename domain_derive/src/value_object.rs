@@ -1,63 +1,102 @@
-use syn::{DeriveInput, Data, Error};
+use syn::{parse_quote, DeriveInput, Data, Error, Field, Fields, Index, Lit, LitInt, LitStr, Member, Meta, MetaNameValue, Path, Token, Type};
+use syn::spanned::Spanned;
+use crate::command;
 use syn::Ident;
 use syn::export::Span;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::quote;
 
-/// `precondition` checks all invariants for the Struct structure that the macro is being applied to.
+/// `precondition` checks all invariants for the structure that the macro is being applied to.
 /// The following conditions must be true:
-/// 1. There needs to be a value field.
+/// 1. It needs to be a tuple struct with a single field, a struct with named fields, or an enum of unit variants.
+/// 2. There needs to be at least one field or variant.
 pub fn precondition(input: &DeriveInput) -> Result<(), syn::Error> {
-    check_value_field(input)?;
+    check_fields(input)?;
 
     Ok(())
 }
 
-fn check_value_field(input: &DeriveInput) -> Result<(), syn::Error> {
-    if has_value_field(&input.data) && has_one_field(&input.data) {
-        return Ok(());
+fn check_fields(input: &DeriveInput) -> Result<(), syn::Error> {
+    if let Data::Struct(st) = &input.data {
+        match &st.fields {
+            Fields::Named(named) if !named.named.is_empty() => return Ok(()),
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => return Ok(()),
+            _ => (),
+        }
+    }
+
+    if let Data::Enum(en) = &input.data {
+        if let Some(variant) = en.variants.iter().find(|v| !matches!(v.fields, Fields::Unit)) {
+            return Err(Error::new(variant.fields.span(), "enum value objects can only have unit variants"));
+        }
+        if !en.variants.is_empty() {
+            return Ok(());
+        }
     }
 
     let input_span = input.ident.span();
-    Err(Error::new(input_span, "expected a struct with a single field named `value`."))
+    Err(Error::new(
+        input_span,
+        "expected a tuple struct with a single field, a struct with a single field named `value`, a struct with named fields, or an enum of unit variants.",
+    ))
 }
 
-fn has_one_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().len() == 1
-        },
+/// `is_composite` returns true if the value object is a struct holding several fields, rather than a single value.
+pub fn is_composite(input: &DeriveInput) -> bool {
+    match &input.data {
+        Data::Struct(_) => value_field(&input.data).is_none(),
         _ => false,
     }
 }
 
-fn has_value_field(data: &Data) -> bool {
-    match data {
-        Data::Struct(st) => {
-            st.fields.iter().any(|f| {
-                f.clone().ident.unwrap() == "value"
-            })
-        },
-        _ => false,
-    }
+/// `is_enum` returns true if the value object is an enum.
+pub fn is_enum(input: &DeriveInput) -> bool {
+    matches!(input.data, Data::Enum(_))
 }
 
-// returns the path name of the type used for the value field.
-pub fn value_type_name(data: &Data) -> Option<Ident> {
-    if let Data::Struct(st) = data {
-        let result = st.fields.iter().find(|f| {
-            let ident = f.clone().ident.clone().unwrap();
-            ident.to_string().contains("value")
-        });
+/// `value_field` returns the member and type of the field holding the value, for value objects that hold a single
+/// value, either as a tuple struct or in a field named `value`.
+pub fn value_field(data: &Data) -> Option<(Member, Type)> {
+    let st = match data {
+        Data::Struct(st) => st,
+        _ => return None,
+    };
+    if st.fields.iter().len() != 1 {
+        return None;
+    }
 
-        match &result.unwrap().ty {
-            syn::Type::Path(type_path) => {
-                let path_name = type_path.path.segments.iter().next().unwrap().ident.clone();
-                return Some(path_name);
-            },
-            _ => return None,
+    let field = st.fields.iter().next()?;
+    match &field.ident {
+        Some(ident) if ident == "value" => Some((Member::Named(ident.clone()), field.ty.clone())),
+        Some(_) => None,
+        None => Some((Member::Unnamed(Index::from(0)), field.ty.clone())),
+    }
+}
+
+// A bare type parameter can't be the value, as `TryFrom<T>` would conflict with the blanket impl in std.
+fn check_not_type_param(input: &DeriveInput, ty: &Type) -> Result<(), syn::Error> {
+    if let Type::Path(type_path) = ty {
+        if let Some(ident) = type_path.path.get_ident() {
+            if input.generics.type_params().any(|param| &param.ident == ident) {
+                return Err(Error::new(
+                    ident.span(),
+                    "the value can't be a bare type parameter, as `TryFrom` would conflict with the blanket impl in std",
+                ));
+            }
         }
     }
 
-    None
+    Ok(())
+}
+
+// returns true if the type is an `Option`, however it's qualified.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.iter().last().is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
 }
 
 pub fn error_name_from_type(type_name: &Ident, span: Span) -> Ident {
@@ -65,3 +104,827 @@ pub fn error_name_from_type(type_name: &Ident, span: Span) -> Ident {
     let error_name_str = format!("{}ValidationError", name_str);
     Ident::new(&error_name_str, span)
 }
+
+/// `produce_try_from` implements `TryFrom` for the value object.  If an error was given with
+/// `#[value_object(error = path)]` then that is the error type, and the `ValueError` must convert into it.
+/// Otherwise a `<Name>ValidationError` holding the `ValidationErrors` is generated alongside the value object.
+///
+/// `construct` is the expression that builds the value object from the validated `value`.
+pub fn produce_try_from(input: &DeriveInput, type_name: &TokenStream2, construct: &TokenStream2, options: &Options) -> TokenStream2 {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let normalize = produce_normalize(options);
+
+    let (error, validate, error_type) = match &options.error {
+        Some(error) => (
+            quote! { #error },
+            quote! { <Self as domain_patterns::models::ValueObject<#type_name>>::validate(&value)?; },
+            quote! {},
+        ),
+        None => {
+            let error = error_name_from_type(name, name.span());
+            let doc = format!("{} is returned when a `{}` fails validation.", error, name);
+            (
+                quote! { #error },
+                quote! { <Self as domain_patterns::models::FieldValidation<#type_name>>::validate_field("", &value)?; },
+                quote! {
+                    #[doc = #doc]
+                    #[derive(Debug, Clone, PartialEq)]
+                    #vis struct #error {
+                        pub errors: domain_patterns::models::ValidationErrors,
+                    }
+
+                    impl std::fmt::Display for #error {
+                        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                            write!(f, "{}", self.errors)
+                        }
+                    }
+
+                    impl std::error::Error for #error {}
+
+                    impl std::convert::From<domain_patterns::models::ValidationErrors> for #error {
+                        fn from(errors: domain_patterns::models::ValidationErrors) -> Self {
+                            #error { errors }
+                        }
+                    }
+
+                    impl std::convert::From<#error> for domain_patterns::models::ValidationErrors {
+                        fn from(error: #error) -> Self {
+                            error.errors
+                        }
+                    }
+                },
+            )
+        },
+    };
+
+    quote! {
+        impl #impl_generics std::convert::TryFrom<#type_name> for #name #ty_generics #where_clause {
+            type Error = #error;
+
+            fn try_from(value: #type_name) -> std::result::Result<Self, Self::Error> {
+                #normalize
+                #validate
+
+                Ok(#construct)
+            }
+        }
+
+        #error_type
+    }
+}
+
+/// `produce_serde` implements `Serialize` and `Deserialize` when `#[value_object(serde)]` is given.  The value
+/// object is serialized as its value, and deserialized by reading the value and passing it through `TryFrom`, so
+/// invalid values fail deserialization with the value object's error message.
+pub fn produce_serde(input: &DeriveInput, type_name: &TokenStream2, options: &Options) -> TokenStream2 {
+    if !options.serde {
+        return quote! {};
+    }
+
+    let name = &input.ident;
+
+    let mut serialize_generics = input.generics.clone();
+    serialize_generics.make_where_clause().predicates.push(parse_quote! { #type_name: serde::Serialize });
+    let (impl_generics, ty_generics, serialize_where_clause) = serialize_generics.split_for_impl();
+
+    let mut deserialize_generics = input.generics.clone();
+    deserialize_generics.params.insert(0, parse_quote! { 'de });
+    deserialize_generics.make_where_clause().predicates.push(parse_quote! { #type_name: serde::Deserialize<'de> });
+    let (deserialize_impl_generics, _, deserialize_where_clause) = deserialize_generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics serde::Serialize for #name #ty_generics #serialize_where_clause {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where S: serde::Serializer
+            {
+                serde::Serialize::serialize(&domain_patterns::models::ValueObject::<#type_name>::value(self), serializer)
+            }
+        }
+
+        impl #deserialize_impl_generics serde::Deserialize<'de> for #name #ty_generics #deserialize_where_clause {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+                where D: serde::Deserializer<'de>
+            {
+                let value = <#type_name as serde::Deserialize<'de>>::deserialize(deserializer)?;
+                <Self as std::convert::TryFrom<#type_name>>::try_from(value).map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
+/// `produce_arbitrary` implements proptest's `Arbitrary` when `#[value_object(arbitrary)]` is given.  Inputs are
+/// generated by the function given with `#[value_object(arbitrary = path)]`, or otherwise by `inputs`, and passed
+/// through `TryFrom`, so only valid value objects are generated.
+///
+/// When `invalid_inputs` is given, `ValueStrategy` is also implemented, generating the inputs that fail validation.
+pub fn produce_arbitrary(
+    input: &DeriveInput,
+    type_name: &TokenStream2,
+    inputs: TokenStream2,
+    invalid_inputs: Option<TokenStream2>,
+    options: &Options,
+) -> TokenStream2 {
+    if !options.arbitrary {
+        return quote! {};
+    }
+
+    let name = &input.ident;
+    let proptest = quote! { domain_patterns::strategy::proptest };
+    let inputs = match &options.inputs {
+        Some(path) => quote! { #path() },
+        None => inputs,
+    };
+
+    let mut generics = input.generics.clone();
+    generics.make_where_clause().predicates.push(parse_quote! { Self: std::fmt::Debug + 'static });
+    generics.make_where_clause().predicates.push(parse_quote! { #type_name: std::fmt::Debug + 'static });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let value_strategy = match invalid_inputs {
+        Some(invalid_inputs) => quote! {
+            impl #impl_generics domain_patterns::strategy::ValueStrategy<#type_name> for #name #ty_generics #where_clause {
+                fn invalid() -> #proptest::strategy::BoxedStrategy<#type_name> {
+                    domain_patterns::strategy::invalid::<Self, #type_name, _>(#invalid_inputs)
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    quote! {
+        impl #impl_generics #proptest::arbitrary::Arbitrary for #name #ty_generics #where_clause {
+            type Parameters = ();
+            type Strategy = #proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                domain_patterns::strategy::valid::<Self, #type_name, _>(#inputs)
+            }
+        }
+
+        #value_strategy
+    }
+}
+
+// Returns the range as a range of the value's type, as proptest can't infer the type of integer literals in a range
+// used as a strategy.  Ranges that aren't written with `..` or `..=`, such as a constant, are returned as is.
+fn typed_range(range: &TokenStream2, type_name: &TokenStream2) -> TokenStream2 {
+    let tokens: Vec<TokenTree> = range.clone().into_iter().collect();
+    let is_dot = |i: usize| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == '.');
+    let split = match (0..tokens.len()).find(|i| is_dot(*i) && is_dot(i + 1)) {
+        Some(split) => split,
+        None => return quote! { (#range) },
+    };
+
+    let inclusive = matches!(tokens.get(split + 2), Some(TokenTree::Punct(p)) if p.as_char() == '=');
+    let from: TokenStream2 = tokens[..split].iter().cloned().collect();
+    let to: TokenStream2 = tokens[split + if inclusive { 3 } else { 2 }..].iter().cloned().collect();
+
+    match (from.is_empty(), to.is_empty(), inclusive) {
+        (false, false, true) => quote! { std::ops::RangeInclusive::<#type_name>::new(#from, #to) },
+        (false, false, false) => quote! { std::ops::Range::<#type_name> { start: #from, end: #to } },
+        (false, true, _) => quote! { std::ops::RangeFrom::<#type_name> { start: #from } },
+        (true, false, true) => quote! { std::ops::RangeToInclusive::<#type_name> { end: #to } },
+        (true, false, false) => quote! { std::ops::RangeTo::<#type_name> { end: #to } },
+        (true, true, _) => quote! { (#range) },
+    }
+}
+
+// Returns the strategies for valid and invalid inputs of a value object holding a single value, based on its
+// validators.  Length validators only shape the inputs of `String` values, as other types can't be generated from
+// a length.
+fn newtype_inputs(value_type: &Type, type_name: &TokenStream2, options: &Options) -> (TokenStream2, TokenStream2) {
+    let any = quote! { domain_patterns::strategy::proptest::arbitrary::any::<#type_name>() };
+    let is_string = match value_type {
+        Type::Path(type_path) => type_path.path.segments.iter().last().is_some_and(|s| s.ident == "String"),
+        _ => false,
+    };
+    let min = match &options.min_len {
+        Some(len) => quote! { Some(#len) },
+        None => quote! { None },
+    };
+    let max = match &options.max_len {
+        Some(len) => quote! { Some(#len) },
+        None => quote! { None },
+    };
+
+    let inputs = if let Some(regex) = &options.regex {
+        quote! { domain_patterns::strategy::matching(#regex) }
+    } else if let Some(range) = &options.range {
+        typed_range(range, type_name)
+    } else if is_string && (options.min_len.is_some() || options.max_len.is_some()) {
+        quote! { domain_patterns::strategy::lengths(#min, #max) }
+    } else {
+        any.clone()
+    };
+
+    let min_above_zero = options.min_len.as_ref().is_some_and(|len| len.base10_digits() != "0");
+    let invalid_inputs = if is_string && (min_above_zero || options.max_len.is_some()) {
+        quote! {
+            domain_patterns::strategy::proptest::strategy::Union::new(vec![
+                domain_patterns::strategy::proptest::strategy::Strategy::boxed(#any),
+                domain_patterns::strategy::wrong_lengths(#min, #max),
+            ])
+        }
+    } else {
+        any
+    };
+
+    (inputs, invalid_inputs)
+}
+
+/// A single option from a `#[value_object(..)]` attribute.
+enum ValueOption {
+    Code(LitStr),
+    Error(Path),
+    Check(Path),
+    Display(LitStr),
+    Regex(LitStr),
+    MinLen(LitInt),
+    MaxLen(LitInt),
+    Range(TokenStream2),
+    Trim,
+    Lowercase,
+    Serde,
+    Arbitrary(Option<Path>),
+}
+
+impl Parse for ValueOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        match name.to_string().as_str() {
+            "trim" => return Ok(ValueOption::Trim),
+            "lowercase" => return Ok(ValueOption::Lowercase),
+            "serde" => return Ok(ValueOption::Serde),
+            "arbitrary" if !input.peek(Token![=]) => return Ok(ValueOption::Arbitrary(None)),
+            _ => (),
+        }
+
+        input.parse::<Token![=]>()?;
+        match name.to_string().as_str() {
+            "code" => Ok(ValueOption::Code(input.parse()?)),
+            "error" => Ok(ValueOption::Error(input.parse()?)),
+            "check" => Ok(ValueOption::Check(input.parse()?)),
+            "display" => Ok(ValueOption::Display(input.parse()?)),
+            "regex" => {
+                // Compiled here so a bad pattern is a compile error rather than a panic on the first `validate`.
+                let regex: LitStr = input.parse()?;
+                if let Err(e) = regex::Regex::new(&regex.value()) {
+                    return Err(Error::new(regex.span(), e));
+                }
+                Ok(ValueOption::Regex(regex))
+            },
+            "min_len" => Ok(ValueOption::MinLen(input.parse()?)),
+            "max_len" => Ok(ValueOption::MaxLen(input.parse()?)),
+            "arbitrary" => Ok(ValueOption::Arbitrary(Some(input.parse()?))),
+            "range" => {
+                // The range is handed to the generated code as is, so take everything up to the next option.
+                let mut range = TokenStream2::new();
+                while !input.is_empty() && !input.peek(Token![,]) {
+                    range.extend(Some(input.parse::<TokenTree>()?));
+                }
+                if range.is_empty() {
+                    return Err(Error::new(name.span(), "expected a range, such as `range = 0..=100`"));
+                }
+                Ok(ValueOption::Range(range))
+            },
+            _ => Err(Error::new(
+                name.span(),
+                "unknown value_object option, expected one of `code`, `error`, `check`, `display`, `regex`, `min_len`, `max_len`, `range`, `trim`, `lowercase`, `serde` or `arbitrary`",
+            )),
+        }
+    }
+}
+
+/// The options given to `ValueSetup` with `#[value_object(..)]` attributes.
+#[derive(Default)]
+pub struct Options {
+    code: Option<LitStr>,
+    error: Option<Path>,
+    checks: Vec<Path>,
+    display: Option<LitStr>,
+    regex: Option<LitStr>,
+    min_len: Option<LitInt>,
+    max_len: Option<LitInt>,
+    range: Option<TokenStream2>,
+    trim: bool,
+    lowercase: bool,
+    serde: bool,
+    arbitrary: bool,
+    inputs: Option<Path>,
+}
+
+impl Options {
+    /// Returns true if the value should be normalized before it is validated and stored.
+    pub fn normalizes(&self) -> bool {
+        self.trim || self.lowercase
+    }
+
+    /// Returns true if any validator or normalizer was given, in which case the whole `ValueObject` impl is generated.
+    pub fn generates_value_object(&self) -> bool {
+        self.normalizes() || self.regex.is_some() || self.min_len.is_some() || self.max_len.is_some() || self.range.is_some()
+    }
+
+    /// Returns an error if options that only apply to the other kind of value object were given.
+    fn check_applicable(&self, input: &DeriveInput, composite: bool) -> Result<(), syn::Error> {
+        if composite && self.generates_value_object() {
+            return Err(Error::new(
+                input.ident.span(),
+                "`regex`, `min_len`, `max_len`, `range`, `trim` and `lowercase` only apply to a single `value`, use `#[validate(..)]` on fields and `check` instead",
+            ));
+        }
+        if composite && self.serde {
+            return Err(Error::new(input.ident.span(), "`serde` only applies to value objects holding a single value, or enums"));
+        }
+        if !composite && (!self.checks.is_empty() || self.display.is_some()) {
+            return Err(Error::new(input.ident.span(), "`check` and `display` only apply to value objects with several fields"));
+        }
+
+        Ok(())
+    }
+}
+
+/// `parse_options` collects the options from every `#[value_object(..)]` attribute on the struct.
+pub fn parse_options(input: &DeriveInput) -> Result<Options, syn::Error> {
+    let mut options = Options::default();
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident("value_object")) {
+        let parsed = attr.parse_args_with(Punctuated::<ValueOption, Token![,]>::parse_terminated)?;
+        for option in parsed {
+            match option {
+                ValueOption::Code(code) => options.code = Some(code),
+                ValueOption::Error(error) => options.error = Some(error),
+                ValueOption::Check(check) => options.checks.push(check),
+                ValueOption::Display(display) => options.display = Some(display),
+                ValueOption::Regex(regex) => options.regex = Some(regex),
+                ValueOption::MinLen(len) => options.min_len = Some(len),
+                ValueOption::MaxLen(len) => options.max_len = Some(len),
+                ValueOption::Range(range) => options.range = Some(range),
+                ValueOption::Trim => options.trim = true,
+                ValueOption::Lowercase => options.lowercase = true,
+                ValueOption::Serde => options.serde = true,
+                ValueOption::Arbitrary(inputs) => {
+                    options.arbitrary = true;
+                    options.inputs = inputs;
+                },
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+/// `produce_field_validation` implements `FieldValidation` for the value object, using the code given with
+/// `#[value_object(code = "..")]`, or otherwise `invalid_` followed by the snake cased struct name.
+///
+/// When the `ValueObject` impl is generated its errors are already `ValidationErrors`, so they're nested under
+/// the field path rather than wrapped in a single error.
+pub fn produce_field_validation(input: &DeriveInput, type_name: &TokenStream2, generated: bool, options: &Options) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let code = match &options.code {
+        Some(code) => code.value(),
+        None => format!("invalid_{}", snake_case(&name.to_string())),
+    };
+
+    let validate_field = if generated {
+        quote! {
+            fn validate_field(path: &str, value: &#type_name) -> std::result::Result<(), domain_patterns::models::ValidationErrors> {
+                <Self as domain_patterns::models::ValueObject<#type_name>>::validate(value).map_err(|e| e.nest(path))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        impl #impl_generics domain_patterns::models::FieldValidation<#type_name> for #name #ty_generics #where_clause {
+            fn code() -> &'static str {
+                #code
+            }
+
+            #validate_field
+        }
+    }
+}
+
+/// `produce_normalize` returns the statement that normalizes `value` before it is validated, if any normalizer
+/// was given.
+pub fn produce_normalize(options: &Options) -> TokenStream2 {
+    let trim = if options.trim {
+        quote! { let value = value.trim().to_string(); }
+    } else {
+        quote! {}
+    };
+    let lowercase = if options.lowercase {
+        quote! { let value = value.to_lowercase(); }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #trim
+        #lowercase
+    }
+}
+
+/// `produce_value_object` generates the whole `ValueObject` impl from the validators given with
+/// `#[value_object(..)]`.  Every validator is checked, and every failure is collected into `ValidationErrors`.
+pub fn produce_value_object(input: &DeriveInput, member: &Member, type_name: &TokenStream2, options: &Options) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let normalize = produce_normalize(options);
+
+    let mut checks = vec![];
+    if let Some(len) = &options.min_len {
+        checks.push(quote! {
+            if value.chars().count() < #len {
+                errors.add("", "min_len", &format!("must have a length of at least {}", #len));
+            }
+        });
+    }
+    if let Some(len) = &options.max_len {
+        checks.push(quote! {
+            if value.chars().count() > #len {
+                errors.add("", "max_len", &format!("must have a length of at most {}", #len));
+            }
+        });
+    }
+    if let Some(range) = &options.range {
+        let range_str = range.to_string().replace(' ', "");
+        checks.push(quote! {
+            if !(#range).contains(&value) {
+                errors.add("", "range", &format!("must be within {}", #range_str));
+            }
+        });
+    }
+    if let Some(regex) = &options.regex {
+        checks.push(quote! {
+            static PATTERN: std::sync::OnceLock<domain_patterns::regex::Regex> = std::sync::OnceLock::new();
+            let pattern = PATTERN.get_or_init(|| domain_patterns::regex::Regex::new(#regex).expect("regex was checked when the value object was derived"));
+            if !pattern.is_match(&value) {
+                errors.add("", "regex", "does not match the expected format");
+            }
+        });
+    }
+
+    quote! {
+        impl #impl_generics domain_patterns::models::ValueObject<#type_name> for #name #ty_generics #where_clause {
+            type ValueError = domain_patterns::models::ValidationErrors;
+
+            fn validate(value: &#type_name) -> std::result::Result<(), Self::ValueError> {
+                let value = value.clone();
+                #normalize
+                let mut errors = domain_patterns::models::ValidationErrors::new();
+
+                #(#checks)*
+
+                errors.into_result()
+            }
+
+            fn value(&self) -> #type_name {
+                self.#member.clone()
+            }
+        }
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
+/// `produce_newtype` generates the impls for a value object holding a single value, either as a tuple struct or in
+/// a field named `value`.  The value can be of any type.  An `Option` is displayed as its inner value, or as an
+/// empty string if there is none.
+pub fn produce_newtype(input: &DeriveInput, options: &Options) -> Result<TokenStream2, syn::Error> {
+    options.check_applicable(input, false)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // safe to unwrap because we know the value object holds a single value.
+    let (member, value_type) = value_field(&input.data).unwrap();
+    check_not_type_param(input, &value_type)?;
+    let type_name = quote! { #value_type };
+
+    let display = if is_option(&value_type) {
+        quote! {
+            match &self.#member {
+                Some(value) => write!(f, "{}", value),
+                None => Ok(()),
+            }
+        }
+    } else {
+        quote! { write!(f, "{}", self.#member) }
+    };
+
+    let generated = options.generates_value_object();
+    let field_validation = produce_field_validation(input, &type_name, generated, options);
+    let try_from = produce_try_from(input, &type_name, &quote! { Self { #member: value } }, options);
+    let serde = produce_serde(input, &type_name, options);
+    let (inputs, invalid_inputs) = newtype_inputs(&value_type, &type_name, options);
+    let arbitrary = produce_arbitrary(input, &type_name, inputs, Some(invalid_inputs), options);
+    let value_object = if generated {
+        produce_value_object(input, &member, &type_name, options)
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                #display
+            }
+        }
+
+        impl #impl_generics std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                self.#member == other.#member
+            }
+        }
+
+        impl #impl_generics std::clone::Clone for #name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                Self {
+                    #member: self.#member.clone()
+                }
+            }
+        }
+
+        #try_from
+
+        #value_object
+
+        #field_validation
+
+        #serde
+
+        #arbitrary
+    })
+}
+
+/// `produce_composite` generates the impls for a value object holding several fields.  The value object is a
+/// `ValueObject` of the tuple of its field types, in declaration order, and gets a validating `new` constructor
+/// that takes every field.
+///
+/// Each field is checked against the rules in its `#[validate(..)]` attributes, reporting failures at the field's
+/// name.  If every field passed, the whole object is checked by each `#[value_object(check = path)]` function,
+/// which takes `&Self` and returns `Result<(), ValidationErrors>`.
+pub fn produce_composite(input: &DeriveInput, options: &Options) -> Result<TokenStream2, syn::Error> {
+    options.check_applicable(input, true)?;
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields: Vec<&Field> = match &input.data {
+        Data::Struct(st) => st.fields.iter().collect(),
+        _ => vec![],
+    };
+    // Safe to unwrap because we check for named fields in precondition.
+    let idents: Vec<&Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+    let type_name = quote! { (#(#types,)*) };
+
+    let mut field_checks = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let index = Index::from(i);
+        for rule in command::field_rules(field)? {
+            field_checks.push(command::implement_rule(&rule, &quote! { value.#index }, &idents[i].to_string()));
+        }
+    }
+    let checks = &options.checks;
+
+    let display = match &options.display {
+        Some(format) => {
+            // Only pass the fields the format string uses, as unused named arguments don't compile.
+            let used: Vec<&&Ident> = idents.iter()
+                .filter(|ident| {
+                    let value = format.value();
+                    value.contains(&format!("{{{}}}", ident)) || value.contains(&format!("{{{}:", ident))
+                })
+                .collect();
+            quote! { write!(f, #format, #(#used = self.#used),*) }
+        },
+        None => {
+            let format = vec!["{}"; idents.len()].join(", ");
+            quote! { write!(f, #format, #(self.#idents),*) }
+        },
+    };
+
+    let construct = quote! {
+        {
+            let (#(#idents,)*) = value;
+            Self { #(#idents),* }
+        }
+    };
+    let try_from = produce_try_from(input, &type_name, &construct, options);
+    let field_validation = produce_field_validation(input, &type_name, true, options);
+    let arbitrary = produce_arbitrary(
+        input,
+        &type_name,
+        quote! { domain_patterns::strategy::proptest::arbitrary::any::<#type_name>() },
+        None,
+        options,
+    );
+    let error = match &options.error {
+        Some(error) => quote! { #error },
+        None => {
+            let error = error_name_from_type(name, name.span());
+            quote! { #error }
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                #display
+            }
+        }
+
+        impl #impl_generics std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                #(self.#idents == other.#idents)&&*
+            }
+        }
+
+        impl #impl_generics std::clone::Clone for #name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                Self {
+                    #(#idents: self.#idents.clone()),*
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Creates the value object from its fields, validating each field and then the whole object.
+            #vis fn new(#(#idents: #types),*) -> std::result::Result<Self, #error> {
+                <Self as std::convert::TryFrom<#type_name>>::try_from((#(#idents,)*))
+            }
+        }
+
+        impl #impl_generics domain_patterns::models::ValueObject<#type_name> for #name #ty_generics #where_clause {
+            type ValueError = domain_patterns::models::ValidationErrors;
+
+            fn validate(value: &#type_name) -> std::result::Result<(), Self::ValueError> {
+                let mut errors = domain_patterns::models::ValidationErrors::new();
+
+                #(#field_checks)*
+
+                if errors.is_empty() {
+                    let (#(#idents,)*) = value.clone();
+                    let candidate = Self { #(#idents),* };
+                    #(errors.check(#checks(&candidate));)*
+                }
+
+                errors.into_result()
+            }
+
+            fn value(&self) -> #type_name {
+                (#(self.#idents.clone(),)*)
+            }
+        }
+
+        #try_from
+
+        #field_validation
+
+        #arbitrary
+    })
+}
+
+/// `produce_enum` generates the impls for an enum value object, where every unit variant is represented by the
+/// string given in its `#[value = ".."]` attribute.  The enum is a `ValueObject<String>`, which fails validation for
+/// any string that isn't one of the variants' values, and also gets `FromStr` and an `all_values()` list.
+pub fn produce_enum(input: &DeriveInput, options: &Options) -> Result<TokenStream2, syn::Error> {
+    if options.generates_value_object() || !options.checks.is_empty() || options.display.is_some() {
+        return Err(Error::new(input.ident.span(), "enum value objects only support the `code`, `error`, `serde` and `arbitrary` options"));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let variants = match &input.data {
+        Data::Enum(en) => &en.variants,
+        _ => return Err(Error::new(input.ident.span(), "expected an enum")),
+    };
+
+    let mut idents = vec![];
+    let mut values = vec![];
+    for variant in variants.iter() {
+        let value = variant.attrs.iter()
+            .find(|a| a.path.is_ident("value"))
+            .ok_or_else(|| Error::new(variant.ident.span(), "expected a `#[value = \"..\"]` attribute on the variant"))?;
+        match value.parse_meta()? {
+            Meta::NameValue(MetaNameValue { lit: Lit::Str(lit), .. }) => values.push(lit),
+            meta => return Err(Error::new(meta.span(), "expected a string value, such as `#[value = \"active\"]`")),
+        }
+        idents.push(&variant.ident);
+    }
+    let expected = format!(
+        "must be one of {}",
+        values.iter().map(|v| v.value()).collect::<Vec<String>>().join(", "),
+    );
+
+    let type_name = quote! { String };
+    let construct = quote! {
+        match value.as_str() {
+            #(#values => #name::#idents,)*
+            _ => unreachable!("value was validated"),
+        }
+    };
+    let try_from = produce_try_from(input, &type_name, &construct, options);
+    let field_validation = produce_field_validation(input, &type_name, true, options);
+    let serde = produce_serde(input, &type_name, options);
+    let arbitrary = produce_arbitrary(
+        input,
+        &type_name,
+        quote! {
+            domain_patterns::strategy::proptest::strategy::Strategy::prop_map(
+                domain_patterns::strategy::proptest::sample::select(Self::all_values()),
+                String::from,
+            )
+        },
+        Some(quote! { domain_patterns::strategy::proptest::arbitrary::any::<String>() }),
+        options,
+    );
+    let error = match &options.error {
+        Some(error) => quote! { #error },
+        None => {
+            let error = error_name_from_type(name, name.span());
+            quote! { #error }
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    #(#name::#idents => write!(f, "{}", #values),)*
+                }
+            }
+        }
+
+        impl #impl_generics std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+        }
+
+        impl #impl_generics std::clone::Clone for #name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                match self {
+                    #(#name::#idents => #name::#idents,)*
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Returns the value of every variant, in declaration order.
+            pub fn all_values() -> &'static [&'static str] {
+                &[#(#values),*]
+            }
+        }
+
+        impl #impl_generics std::str::FromStr for #name #ty_generics #where_clause {
+            type Err = #error;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                <Self as std::convert::TryFrom<String>>::try_from(s.to_string())
+            }
+        }
+
+        impl #impl_generics domain_patterns::models::ValueObject<String> for #name #ty_generics #where_clause {
+            type ValueError = domain_patterns::models::ValidationErrors;
+
+            fn validate(value: &String) -> std::result::Result<(), Self::ValueError> {
+                match value.as_str() {
+                    #(#values)|* => Ok(()),
+                    _ => Err(domain_patterns::models::ValidationErrors::single("", "one_of", #expected)),
+                }
+            }
+
+            fn value(&self) -> String {
+                self.to_string()
+            }
+        }
+
+        #try_from
+
+        #field_validation
+
+        #serde
+
+        #arbitrary
+    })
+}
@@ -0,0 +1,7 @@
+// Each file in `compile_fail` breaks one documented precondition of a derive, and the `.stderr` file next to it
+// holds the expected error.  Run with `TRYBUILD=overwrite` to regenerate them after changing a message.
+#[test]
+fn test_preconditions_fail_to_compile() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
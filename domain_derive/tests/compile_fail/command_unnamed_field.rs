@@ -0,0 +1,7 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(Command)]
+struct RenameCommand(#[validate(non_empty)] String);
+
+fn main() {}
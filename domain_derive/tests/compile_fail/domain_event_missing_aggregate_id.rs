@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+struct UserCreatedEvent {
+    id: String,
+    user_id: String,
+    version: u64,
+    occurred: i64,
+}
+
+fn main() {}
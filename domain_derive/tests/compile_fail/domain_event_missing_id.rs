@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+struct UserCreatedEvent {
+    aggregate_id: String,
+    version: u64,
+    occurred: i64,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+struct UserCreatedEvent(String, String, u64, i64);
+
+fn main() {}
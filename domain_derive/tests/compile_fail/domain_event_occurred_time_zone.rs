@@ -0,0 +1,14 @@
+#[macro_use]
+extern crate domain_derive;
+
+use chrono::{DateTime, Local};
+
+#[derive(DomainEvent)]
+struct UserCreatedEvent {
+    id: String,
+    aggregate_id: String,
+    version: u64,
+    occurred: DateTime<Local>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+#[event(aggregate_id = "user_id", occurred = "created_at")]
+struct UserCreatedEvent {
+    id: String,
+    user_id: String,
+    version: u64,
+    occurred: i64,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+#[event(timestamp = "at")]
+struct UserCreatedEvent {
+    id: String,
+    aggregate_id: String,
+    version: u64,
+    at: i64,
+}
+
+fn main() {}
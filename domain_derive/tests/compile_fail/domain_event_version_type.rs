@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvent)]
+struct UserCreatedEvent {
+    id: String,
+    aggregate_id: String,
+    version: f64,
+    occurred: i64,
+}
+
+fn main() {}
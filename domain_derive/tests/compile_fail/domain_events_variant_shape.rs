@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(DomainEvents)]
+enum UserEvents {
+    UserDeleted,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(Entity)]
+struct User {
+    user_id: String,
+    version: u64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(Entity)]
+enum User {
+    Active,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(Entity)]
+struct User {
+    id: String,
+    version: u64,
+    pub name: String,
+}
+
+fn main() {}
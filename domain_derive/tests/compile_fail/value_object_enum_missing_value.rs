@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(ValueSetup)]
+enum Status {
+    #[value = "active"]
+    Active,
+    Closed,
+}
+
+fn main() {}
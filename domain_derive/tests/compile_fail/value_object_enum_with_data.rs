@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(ValueSetup)]
+enum Status {
+    #[value = "active"]
+    Active,
+    #[value = "suspended"]
+    Suspended(String),
+}
+
+fn main() {}
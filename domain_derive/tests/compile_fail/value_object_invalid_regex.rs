@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(ValueSetup)]
+#[value_object(regex = "^[A-Z{3}$")]
+pub struct CurrencyCode(String);
+
+fn main() {}
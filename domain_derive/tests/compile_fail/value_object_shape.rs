@@ -0,0 +1,7 @@
+#[macro_use]
+extern crate domain_derive;
+
+#[derive(ValueSetup)]
+struct Point(i32, i32);
+
+fn main() {}
@@ -4,12 +4,15 @@ extern crate domain_derive;
 #[macro_use]
 extern crate failure;
 
-use domain_patterns::models::{Entity, ValueObject};
+use domain_patterns::models::{Entity, Id, ValueObject, ValidationErrors, FieldValidation, SequentialIdGenerator};
+use domain_patterns::time::{FixedClock, Timestamp};
 use domain_patterns::message::Message;
-use domain_patterns::event::DomainEvent;
+use domain_patterns::event::{DomainEvent, EventConstructorError};
+use domain_patterns::command::Command;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use std::convert::TryFrom;
+use std::time::Duration;
 use regex::Regex;
 
 pub mod entity {
@@ -34,9 +37,25 @@ pub mod entity {
             }
         }
     }
+
+    #[derive(Entity)]
+    pub struct TypedUser {
+        id: domain_patterns::models::Id<TypedUser>,
+        version: u64,
+    }
+
+    impl TypedUser {
+        pub(crate) fn new(id: domain_patterns::models::Id<TypedUser>) -> TypedUser {
+            TypedUser {
+                id,
+                version: 0,
+            }
+        }
+    }
 }
 
 #[derive(ValueSetup)]
+#[value_object(error = Error)]
 pub struct Email {
     pub value: String,
 }
@@ -67,6 +86,127 @@ impl ValueObject<String> for Email {
     }
 }
 
+#[derive(ValueSetup)]
+#[value_object(trim, lowercase, min_len = 3, max_len = 16, regex = r"^[a-z0-9_]+$", serde)]
+pub struct Username {
+    pub value: String,
+}
+
+#[derive(ValueSetup)]
+#[value_object(min_len = 1, max_len = 5)]
+pub struct Title(String);
+
+#[derive(ValueSetup)]
+#[value_object(range = 0..=100)]
+pub struct Percentage {
+    pub value: u8,
+}
+
+#[derive(ValueSetup)]
+#[value_object(trim, lowercase, regex = r"^[a-z0-9-]+$")]
+pub struct Slug(String);
+
+#[derive(ValueSetup)]
+#[value_object(error = Error)]
+pub struct Nickname {
+    pub value: std::option::Option<std::string::String>,
+}
+
+impl ValueObject<Option<String>> for Nickname {
+    type ValueError = Error;
+
+    fn validate(_value: &Option<String>) -> Result<(), Self::ValueError> {
+        Ok(())
+    }
+
+    fn value(&self) -> Option<String> {
+        self.value.clone()
+    }
+}
+
+#[derive(ValueSetup)]
+#[value_object(error = Error, serde)]
+pub struct Label<T: Clone + PartialEq + std::fmt::Display>(Option<T>);
+
+impl<T: Clone + PartialEq + std::fmt::Display> ValueObject<Option<T>> for Label<T> {
+    type ValueError = Error;
+
+    fn validate(_value: &Option<T>) -> Result<(), Self::ValueError> {
+        Ok(())
+    }
+
+    fn value(&self) -> Option<T> {
+        self.0.clone()
+    }
+}
+
+#[derive(ValueSetup)]
+pub struct Address {
+    #[validate(non_empty)]
+    pub street: String,
+    #[validate(non_empty, max_len = 10)]
+    pub zip: String,
+    pub contact: Email,
+}
+
+#[derive(ValueSetup, Debug)]
+#[value_object(check = starts_before_end, display = "{start} to {end}")]
+pub struct DateRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+fn starts_before_end(range: &DateRange) -> Result<(), ValidationErrors> {
+    if range.start > range.end {
+        return Err(ValidationErrors::single("", "starts_after_end", "must start before it ends"));
+    }
+
+    Ok(())
+}
+
+#[derive(ValueSetup, Debug, Copy)]
+#[value_object(serde)]
+pub enum AccountStatus {
+    #[value = "active"]
+    Active,
+    #[value = "suspended"]
+    Suspended,
+    #[value = "closed"]
+    Closed,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Profile {
+    pub username: Username,
+    pub status: AccountStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, DomainEvent)]
+#[event(constructor)]
+pub struct TypedUserRenamedEvent {
+    pub id: Id<TypedUserRenamedEvent>,
+    pub aggregate_id: Id<entity::TypedUser>,
+    pub version: u64,
+    pub occurred: i64,
+}
+
+#[derive(Clone, DomainEvent)]
+pub struct UserSuspendedEvent {
+    pub id: Uuid,
+    pub aggregate_id: String,
+    pub version: u64,
+    pub occurred: std::time::SystemTime,
+}
+
+#[derive(Clone, DomainEvent)]
+#[event(constructor)]
+pub struct UserReinstatedEvent {
+    pub id: Uuid,
+    pub aggregate_id: String,
+    pub version: u64,
+    pub occurred: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Serialize, Deserialize, Clone, DomainEvent)]
 pub struct FirstNameUpdatedEvent {
     pub id: Uuid,
@@ -91,6 +231,13 @@ pub enum UserEvents {
     EmailUpdated(EmailUpdatedEvent),
 }
 
+#[derive(Command)]
+pub struct ChangeEmailCommand {
+    pub id: Uuid,
+    #[validate(non_empty, max_len = 20, value_object = Email)]
+    pub email: String,
+}
+
 //// UNCOMMENT THIS TO CHECK FOR COMPILE TIME FAILIURE.
 //#[derive(DomainEvents)]
 //pub struct NotEvents {}
@@ -123,6 +270,142 @@ fn value_object_setup_macro_works() {
     assert_eq!(email.unwrap().value, "test_email@email.com".to_string());
 }
 
+#[test]
+fn value_object_attributes_normalize_before_validating() {
+    let username = Username::try_from("  Some_User ".to_string()).unwrap();
+    assert_eq!(username.value(), "some_user");
+
+    // Validation sees the normalized value too, so surrounding whitespace doesn't count towards the length.
+    assert!(Username::validate(&"  abc  ".to_string()).is_ok());
+}
+
+#[test]
+fn value_object_attributes_collect_every_failure() {
+    let errors = Username::validate_field("username", &"a!".to_string()).unwrap_err();
+    let codes: Vec<&str> = errors.iter().map(|e| e.code.as_str()).collect();
+    assert_eq!(codes, vec!["min_len", "regex"]);
+    assert!(errors.iter().all(|e| e.path == "username"));
+
+    assert!(Percentage::try_from(100).is_ok());
+    match Percentage::try_from(101) {
+        Err(e) => assert_eq!(e.errors.errors[0].message, "must be within 0..=100"),
+        _ => panic!("expected 101 to be out of range"),
+    }
+}
+
+pub mod nested {
+    use domain_patterns::models::ValidationErrors;
+
+    // A value object in a module with its own error, rather than the crate's root error.
+    #[derive(Debug, PartialEq)]
+    pub struct TagError(pub ValidationErrors);
+
+    impl From<ValidationErrors> for TagError {
+        fn from(errors: ValidationErrors) -> Self {
+            TagError(errors)
+        }
+    }
+
+    #[derive(ValueSetup)]
+    #[value_object(error = TagError, trim, min_len = 1)]
+    pub struct Tag {
+        pub value: String,
+    }
+}
+
+#[test]
+fn value_object_errors_can_be_chosen_or_generated() {
+    match nested::Tag::try_from("   ".to_string()) {
+        Err(nested::TagError(errors)) => assert_eq!(errors.errors[0].code, "min_len"),
+        _ => panic!("expected an empty tag to fail validation"),
+    }
+
+    // Without an error option a `<Name>ValidationError` is generated.
+    let error: UsernameValidationError = Username::try_from("no".to_string()).err().unwrap();
+    assert_eq!(error.to_string(), "validation failed: must have a length of at least 3");
+}
+
+#[test]
+fn value_object_setup_supports_tuple_structs_and_any_inner_type() {
+    let slug = Slug::try_from(" Hello-World ".to_string()).unwrap();
+    assert_eq!(slug.value(), "hello-world");
+    assert!(Slug::try_from("hello world".to_string()).is_err());
+
+    let nickname = Nickname::try_from(None).unwrap();
+    assert_eq!(nickname.to_string(), "");
+    assert_eq!(Nickname::try_from(Some("nick".to_string())).unwrap().to_string(), "nick");
+
+    let label = Label::try_from(Some(42u32)).unwrap();
+    assert_eq!(label.to_string(), "42");
+    assert!(label == Label(Some(42)));
+    assert_eq!(serde_json::to_string(&label).unwrap(), "42");
+    assert!(serde_json::from_str::<Label<u32>>("null").unwrap() == Label(None));
+}
+
+#[test]
+fn composite_value_object_checks_every_field() {
+    let contact = Email::try_from("test_email@email.com".to_string()).unwrap();
+
+    let address = Address::new("1 Main St".to_string(), "12345".to_string(), contact.clone()).unwrap();
+    assert_eq!(address.to_string(), "1 Main St, 12345, test_email@email.com");
+    assert!(address == address.clone());
+    assert_eq!(address.value().1, "12345");
+
+    let errors = Address::new("".to_string(), "12345678901".to_string(), contact).err().unwrap().errors;
+    let failures: Vec<(&str, &str)> = errors.iter().map(|e| (e.path.as_str(), e.code.as_str())).collect();
+    assert_eq!(failures, vec![("street", "non_empty"), ("zip", "max_len")]);
+}
+
+#[test]
+fn length_validators_count_characters() {
+    assert!(Title::try_from("héllo".to_string()).is_ok());
+    assert!(Title::try_from("héllos".to_string()).is_err());
+
+    // Ten characters, but twenty bytes.
+    let contact = Email::try_from("test_email@email.com".to_string()).unwrap();
+    assert!(Address::new("1 Main St".to_string(), "éééééééééé".to_string(), contact).is_ok());
+}
+
+#[test]
+fn composite_value_object_checks_whole_object() {
+    let range = DateRange::new(1, 5).unwrap();
+    assert_eq!(range.to_string(), "1 to 5");
+    assert_eq!(DateRange::try_from((1, 5)).unwrap(), range);
+
+    let errors = DateRange::validate_field("stay", &(5, 1)).unwrap_err();
+    assert_eq!(errors.errors[0].path, "stay");
+    assert_eq!(errors.errors[0].code, "starts_after_end");
+}
+
+#[test]
+fn enum_value_object_maps_variants_to_strings() {
+    let status = AccountStatus::try_from("suspended".to_string()).unwrap();
+    assert_eq!(status, AccountStatus::Suspended);
+    assert_eq!(status.value(), "suspended".to_string());
+    assert_eq!(AccountStatus::Closed.to_string(), "closed");
+    assert_eq!("active".parse::<AccountStatus>().unwrap(), AccountStatus::Active);
+    assert_eq!(AccountStatus::all_values(), &["active", "suspended", "closed"]);
+
+    let errors: ValidationErrors = "deleted".parse::<AccountStatus>().unwrap_err().into();
+    assert_eq!(errors.errors[0].code, "one_of");
+    assert_eq!(errors.errors[0].message, "must be one of active, suspended, closed");
+    assert!(AccountStatus::validate_field("status", &"deleted".to_string()).unwrap_err().for_path("status").len() == 1);
+}
+
+#[test]
+fn serde_option_validates_while_deserializing() {
+    let profile: Profile = serde_json::from_str(r#"{"username":"  Some_User ","status":"active"}"#).unwrap();
+    assert_eq!(profile.username.value(), "some_user".to_string());
+    assert_eq!(profile.status, AccountStatus::Active);
+    assert_eq!(serde_json::to_string(&profile).unwrap(), r#"{"username":"some_user","status":"active"}"#);
+
+    let error = serde_json::from_str::<Profile>(r#"{"username":"no","status":"active"}"#).err().unwrap();
+    assert!(error.to_string().starts_with("validation failed: must have a length of at least 3"));
+
+    let error = serde_json::from_str::<Profile>(r#"{"username":"some_user","status":"deleted"}"#).err().unwrap();
+    assert!(error.to_string().starts_with("validation failed: must be one of active, suspended, closed"));
+}
+
 #[test]
 fn domain_event_macro_works() {
     let updated_event = FirstNameUpdatedEvent {
@@ -135,6 +418,67 @@ fn domain_event_macro_works() {
     assert_eq!(&updated_event.aggregate_id, &updated_event.aggregate_id());
 }
 
+#[test]
+fn derives_accept_typed_ids() {
+    let id: Id<entity::TypedUser> = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+    let user = entity::TypedUser::new(id.clone());
+    assert_eq!(user.id(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+
+    let event = TypedUserRenamedEvent {
+        id: "6f9619ff-8b86-d011-b42d-00cf4fc964ff".parse().unwrap(),
+        aggregate_id: id,
+        version: 1,
+        occurred: 120984128912,
+    };
+    assert_eq!(event.aggregate_id(), user.id());
+    assert_eq!(DomainEvent::id(&event), "6f9619ff-8b86-d011-b42d-00cf4fc964ff");
+}
+
+#[test]
+fn domain_event_constructor_uses_id_generator_and_clock() {
+    let ids = SequentialIdGenerator::new();
+    let clock = FixedClock::new(Timestamp::from_millis(120984128912));
+    let user_id: Id<entity::TypedUser> = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+
+    let first = TypedUserRenamedEvent::new(&ids, &clock, user_id.clone(), 1).unwrap();
+    clock.advance(Duration::from_millis(1));
+    let second = TypedUserRenamedEvent::new(&ids, &clock, user_id, 2).unwrap();
+
+    assert_eq!(DomainEvent::id(&first), "00000000-0000-0000-0000-000000000001");
+    assert_eq!(DomainEvent::id(&second), "00000000-0000-0000-0000-000000000002");
+    assert_eq!(first.occurred(), Timestamp::from_millis(120984128912));
+    assert_eq!(second.occurred(), Timestamp::from_millis(120984128913));
+    assert_eq!(second.version(), 2);
+}
+
+#[test]
+fn domain_event_occurred_accepts_std_and_chrono_times() {
+    let suspended = UserSuspendedEvent {
+        id: Uuid::new_v4(),
+        aggregate_id: "user-1".to_string(),
+        version: 1,
+        occurred: std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_564_660_800_250),
+    };
+    assert_eq!(suspended.occurred().to_string(), "2019-08-01T12:00:00.250Z");
+
+    let clock = FixedClock::new(Timestamp::from_millis(1_564_660_800_250));
+    let reinstated = UserReinstatedEvent::new(&SequentialIdGenerator::new(), &clock, "user-1".to_string(), 2).unwrap();
+    assert_eq!(reinstated.occurred, chrono::DateTime::from_timestamp_millis(1_564_660_800_250).unwrap());
+    assert_eq!(reinstated.occurred(), suspended.occurred());
+}
+
+#[test]
+fn domain_event_constructor_reports_times_out_of_range() {
+    // chrono only covers about 262,000 years either side of the epoch.
+    let clock = FixedClock::new(Timestamp::from_millis(i64::MAX));
+    let result = UserReinstatedEvent::new(&SequentialIdGenerator::new(), &clock, "user-1".to_string(), 2);
+
+    match result {
+        Err(EventConstructorError::Occurred(e)) => assert_eq!(e.timestamp, Timestamp::from_millis(i64::MAX)),
+        _ => panic!("expected the clock's time to be out of range"),
+    }
+}
+
 #[test]
 fn domain_events_macro_works() {
     let updated_event = FirstNameUpdatedEvent {
@@ -155,7 +499,23 @@ fn domain_events_macro_works() {
     let user_event2 = UserEvents::EmailUpdated(updated_event2);
 
     assert_eq!(user_event.version(), 1);
-    assert_eq!(user_event.occurred(), 120984128912);
+    assert_eq!(user_event.occurred(), Timestamp::from_millis(120984128912));
     assert_eq!(user_event2.version(), 2);
-    assert_eq!(user_event2.occurred(), 1209841289888);
+    assert_eq!(user_event2.occurred(), Timestamp::from_millis(1209841289888));
+}
+
+#[test]
+fn command_macro_collects_every_field_error() {
+    let valid = ChangeEmailCommand {
+        id: Uuid::new_v4(),
+        email: "test_email@email.com".to_string(),
+    };
+    assert!(valid.validate().is_ok());
+
+    let invalid = ChangeEmailCommand {
+        id: Uuid::new_v4(),
+        email: "".to_string(),
+    };
+    let codes: Vec<String> = invalid.validate().unwrap_err().errors.into_iter().map(|e| e.code).collect();
+    assert_eq!(codes, vec!["non_empty".to_string(), "value_object".to_string()]);
 }
@@ -1,6 +1,13 @@
 use crate::models::AggregateRoot;
 use std::error::Error;
+use std::fmt;
 use crate::event::DomainEvent;
+use crate::spec::{Fields, QuerySpec};
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::rc::Rc;
 
 /// A trait that provides a collection like abstraction over database access.
 ///
@@ -39,13 +46,18 @@ pub trait Repository<T: AggregateRoot> {
     fn get(&mut self, key: &String) -> Result<Option<T>, Self::Error>;
 
 
-    /// Returns a `Vec<T>` of entities, based on the supplied `page_num` and `page_size`.
+    /// Returns a [`Page`] of entities, based on the supplied `page_num` and `page_size`.
     /// The page_num should start at 1, but is up to the implementer to design as they see fit.
     ///
+    /// Asking for a page past the last page is not an error, and returns a page with no items.  If the
+    /// underlying storage can cheaply count the entities, the total should be set on the page.
+    ///
     /// # Failure case
     ///
     /// If we fail to communicate with the underlying storage, then an error is returned.
-    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Option<Vec<T>>, Self::Error>;
+    ///
+    /// [`Page`]: ./struct.Page.html
+    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Page<T>, Self::Error>;
 
     /// Returns `true` if the underlying storage contains an entity at the specified key,
     /// and otherwise returns `false`.
@@ -103,13 +115,18 @@ pub trait ReadRepository<T> {
     fn get(&mut self, key: &String) -> Result<Option<T>, Self::Error>;
 
 
-    /// Returns a `Vec<T>` of entities, based on the supplied `page_num` and `page_size`.
+    /// Returns a [`Page`] of entities, based on the supplied `page_num` and `page_size`.
     /// The page_num should start at 1, but is up to the implementer to design as they see fit.
     ///
+    /// Asking for a page past the last page is not an error, and returns a page with no items.  If the
+    /// underlying storage can cheaply count the entities, the total should be set on the page.
+    ///
     /// # Failure case
     ///
     /// If we fail to communicate with the underlying storage, then an error is returned.
-    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Option<Vec<T>>, Self::Error>;
+    ///
+    /// [`Page`]: ./struct.Page.html
+    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Page<T>, Self::Error>;
 
     /// Returns `true` if the underlying storage contains an entity at the specified key,
     /// and otherwise returns `false`.
@@ -123,6 +140,155 @@ pub trait ReadRepository<T> {
     fn contains_key(&mut self, key: &String) -> Result<bool, Self::Error> {
         Ok(self.get(key)?.is_some())
     }
+
+    /// Returns every read model matching the filters of the supplied [`QuerySpec`], ordered by its sort keys and
+    /// limited to its limit.  In memory implementations can evaluate the spec with `QuerySpec::apply`, and SQL
+    /// backed implementations can translate it with `QuerySpec::to_sql`.
+    ///
+    /// # Failure case
+    ///
+    /// If we fail to communicate with the underlying storage, then an error is returned.
+    ///
+    /// [`QuerySpec`]: ../spec/struct.QuerySpec.html
+    fn query(&mut self, spec: &QuerySpec) -> Result<Vec<T>, Self::Error>;
+}
+
+/// InMemoryReadRepository is a `ReadRepository` that keeps read models in memory, ordered by key.  It's useful for
+/// tests, and for small read models that are rebuilt from events on startup.
+pub struct InMemoryReadRepository<T> {
+    data: BTreeMap<String, T>,
+}
+
+impl<T: Clone> InMemoryReadRepository<T> {
+    pub fn new() -> InMemoryReadRepository<T> {
+        InMemoryReadRepository {
+            data: BTreeMap::new(),
+        }
+    }
+
+    /// Inserts or replaces the read model at the given key, returning the previous read model if there was one.
+    pub fn insert(&mut self, key: &String, item: &T) -> Option<T> {
+        self.data.insert(key.clone(), item.clone())
+    }
+
+    /// Removes the read model at the given key, returning it if it was present.
+    pub fn remove(&mut self, key: &String) -> Option<T> {
+        self.data.remove(key)
+    }
+}
+
+impl<T: Clone> Default for InMemoryReadRepository<T> {
+    fn default() -> Self {
+        InMemoryReadRepository::new()
+    }
+}
+
+impl<T: Clone + Fields> ReadRepository<T> for InMemoryReadRepository<T> {
+    type Error = Infallible;
+
+    fn get(&mut self, key: &String) -> Result<Option<T>, Self::Error> {
+        Ok(self.data.get(key).cloned())
+    }
+
+    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Page<T>, Self::Error> {
+        let all: Vec<T> = self.data.values().cloned().collect();
+        Ok(Page::from_slice(&all, page_num, page_size))
+    }
+
+    fn query(&mut self, spec: &QuerySpec) -> Result<Vec<T>, Self::Error> {
+        Ok(spec.apply(self.data.values().cloned().collect()))
+    }
+}
+
+/// Page is a single page of results, as returned by `get_paged` on repositories, or by query handlers that page
+/// their results.  Alongside the items it carries the page that was asked for, the total number of results if
+/// the underlying storage knows it, and whether there are more results after this page.
+///
+/// Page derives serde's `Serialize` and `Deserialize`, so it can be returned as-is from a REST controller.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::collections::Page;
+///
+/// let users = vec!["alice", "bob", "carol"];
+///
+/// let page = Page::from_slice(&users, 1, 2);
+/// assert_eq!(page.items, vec!["alice", "bob"]);
+/// assert_eq!(page.total, Some(3));
+/// assert!(page.has_more);
+///
+/// let past_the_end = Page::from_slice(&users, 3, 2);
+/// assert!(past_the_end.is_empty());
+/// assert!(!past_the_end.has_more);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page_info: PageInfo,
+    pub total: Option<usize>,
+    pub has_more: bool,
+}
+
+/// PageInfo describes which page was asked for.  `page_num` starts at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageInfo {
+    pub page_num: usize,
+    pub page_size: usize,
+}
+
+impl<T> Page<T> {
+    /// Creates a page for storage that doesn't know the total number of results.  `has_more` should be
+    /// worked out by the caller, for example by fetching one more item than the page size.
+    pub fn new(items: Vec<T>, page_num: usize, page_size: usize, has_more: bool) -> Page<T> {
+        Page {
+            items,
+            page_info: PageInfo { page_num, page_size },
+            total: None,
+            has_more,
+        }
+    }
+
+    /// Creates a page for storage that knows the total number of results.  Whether there are more results is
+    /// worked out from the total.
+    pub fn with_total(items: Vec<T>, page_num: usize, page_size: usize, total: usize) -> Page<T> {
+        Page {
+            items,
+            page_info: PageInfo { page_num, page_size },
+            total: Some(total),
+            has_more: page_num.max(1).saturating_mul(page_size) < total,
+        }
+    }
+
+    /// Returns `true` if the page holds no items, which is the case for pages past the last page.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the number of items on this page.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Maps the items of the page, keeping the page information.  Useful for turning entities into DTOs.
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            page_info: self.page_info,
+            total: self.total,
+            has_more: self.has_more,
+        }
+    }
+}
+
+impl<T: Clone> Page<T> {
+    /// Cuts a page out of the full list of results.  This is mostly useful for in memory repositories.
+    /// A `page_num` of 0 is treated as the first page.
+    pub fn from_slice(all: &[T], page_num: usize, page_size: usize) -> Page<T> {
+        let start = page_num.saturating_sub(1).saturating_mul(page_size).min(all.len());
+        let end = start.saturating_add(page_size).min(all.len());
+
+        Page::with_total(all[start..end].to_vec(), page_num, page_size, all.len())
+    }
 }
 
 /// EventRepository is a trait that provides collection like semantics over event storage and retrival.  The
@@ -162,6 +328,152 @@ pub trait EventRepository {
     /// Returns a bool letting the caller know if the event repository contains any events associated with the aggregate id.
     fn contains_aggregate(&self, aggregate_id: &String) -> bool;
 
-    /// Inserts a new domain event into the event store.
+    /// Inserts a new domain event into the event store, returning the event if it was inserted, and otherwise
+    /// returning [`None`] if an event with the same id is already stored.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
     fn insert(&mut self, event: &Self::Events) -> Option<Self::Events>;
+
+    /// Returns the current version of the aggregate, which is the highest version of any event stored
+    /// for the aggregate id, or `0` if no events have been stored yet.
+    fn current_version(&self, aggregate_id: &String) -> u64 {
+        self.events_by_aggregate(aggregate_id)
+            .and_then(|events| events.iter().map(|e| e.version()).max())
+            .unwrap_or(0)
+    }
+
+    /// Appends events for an aggregate, but only if the aggregate is still at the `expected_version`.  The
+    /// expected version should be the version the caller loaded the aggregate at before deciding on the new
+    /// events.  If another writer appended events in the meantime, the versions won't match and nothing is
+    /// inserted.
+    ///
+    /// The default implementation checks the version and the event ids first, and then inserts the events one
+    /// at a time.  That isn't atomic, so a store that can be written to concurrently must override it with a
+    /// check and insert that happen in a single transaction.
+    ///
+    /// # Failure case
+    ///
+    /// If the current version of the aggregate does not match `expected_version`, then
+    /// `AppendError::VersionConflict` is returned.  If any of the events has the same id as a stored event, or as
+    /// another of the events, then `AppendError::DuplicateEvent` is returned.
+    fn append(&mut self, aggregate_id: &String, expected_version: u64, events: &[Self::Events]) -> Result<(), AppendError> {
+        let actual_version = self.current_version(aggregate_id);
+        if actual_version != expected_version {
+            return Err(AppendError::VersionConflict(VersionConflictError {
+                aggregate_id: aggregate_id.clone(),
+                expected_version,
+                actual_version,
+            }));
+        }
+
+        let mut event_ids = HashSet::new();
+        for event in events {
+            let event_id = event.id();
+            if self.contains_event(&event_id) || !event_ids.insert(event_id.clone()) {
+                return Err(AppendError::DuplicateEvent { event_id });
+            }
+        }
+
+        for event in events {
+            self.insert(event);
+        }
+
+        Ok(())
+    }
+}
+
+/// A shared event repository, so a command handler can own one handle to the repository while a test or a
+/// projection reads from another.
+impl<R: EventRepository> EventRepository for Rc<RefCell<R>> {
+    type Events = R::Events;
+
+    fn events_by_aggregate(&self, aggregate_id: &String) -> Option<Vec<Self::Events>> {
+        self.borrow().events_by_aggregate(aggregate_id)
+    }
+
+    fn events_since_version(&self, aggregate_id: &String, version: u64) -> Option<Vec<Self::Events>> {
+        self.borrow().events_since_version(aggregate_id, version)
+    }
+
+    fn num_events_since_version(&self, aggregate_id: &String, version: u64, num_events: u64) -> Option<Vec<Self::Events>> {
+        self.borrow().num_events_since_version(aggregate_id, version, num_events)
+    }
+
+    fn get(&self, event_id: &String) -> Option<Self::Events> {
+        self.borrow().get(event_id)
+    }
+
+    fn contains_event(&self, event_id: &String) -> bool {
+        self.borrow().contains_event(event_id)
+    }
+
+    fn contains_aggregate(&self, aggregate_id: &String) -> bool {
+        self.borrow().contains_aggregate(aggregate_id)
+    }
+
+    fn insert(&mut self, event: &Self::Events) -> Option<Self::Events> {
+        self.borrow_mut().insert(event)
+    }
+
+    fn current_version(&self, aggregate_id: &String) -> u64 {
+        self.borrow().current_version(aggregate_id)
+    }
+
+    fn append(&mut self, aggregate_id: &String, expected_version: u64, events: &[Self::Events]) -> Result<(), AppendError> {
+        self.borrow_mut().append(aggregate_id, expected_version, events)
+    }
+}
+
+/// VersionConflictError communicates that events could not be appended for an aggregate, because the aggregate
+/// was not at the version the caller expected.  This is the optimistic concurrency error of an event store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionConflictError {
+    pub aggregate_id: String,
+    pub expected_version: u64,
+    pub actual_version: u64,
+}
+
+impl fmt::Display for VersionConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected aggregate {} to be at version {}, but it is at version {}",
+            self.aggregate_id, self.expected_version, self.actual_version
+        )
+    }
+}
+
+impl Error for VersionConflictError {}
+
+/// AppendError communicates why events could not be appended for an aggregate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppendError {
+    /// The aggregate was not at the version the caller expected.
+    VersionConflict(VersionConflictError),
+    /// An event with the same id is already stored, or was given twice.
+    DuplicateEvent { event_id: String },
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppendError::VersionConflict(conflict) => conflict.fmt(f),
+            AppendError::DuplicateEvent { event_id } => write!(f, "an event with id {} is already stored", event_id),
+        }
+    }
+}
+
+impl Error for AppendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppendError::VersionConflict(conflict) => Some(conflict),
+            AppendError::DuplicateEvent { .. } => None,
+        }
+    }
+}
+
+impl From<VersionConflictError> for AppendError {
+    fn from(conflict: VersionConflictError) -> Self {
+        AppendError::VersionConflict(conflict)
+    }
 }
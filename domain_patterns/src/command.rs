@@ -1,8 +1,22 @@
 use crate::message::Message;
+use crate::models::{Decider, ValidationErrors};
+use crate::event::DomainEvent;
+use crate::collections::{AppendError, EventRepository};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
 /// Command is a simple marker trait for command structs.  These are commands that are issued and handled
 /// by a command handler.  They are things we can say "no" to.
-pub trait Command: Message {}
+pub trait Command: Message {
+    /// validate checks the raw data carried by the command before it is handed to a handler, and
+    /// returns every field that failed validation, with the field name as the path.  The default implementation accepts everything.
+    ///
+    /// The `Command` derive macro from `domain_derive` generates this method from `#[validate(..)]`
+    /// field attributes.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
 
 /// Command handler will handle any generic message.  This could be used in a 1:1 fashion, with only
 /// one handler per command, or you could implement this on a single handler that handles multiple messages,
@@ -14,3 +28,269 @@ pub trait Handles<T: Message> {
 
     fn handle(&mut self, msg: T) -> Self::Result;
 }
+
+/// IdempotentCommand should be implemented by commands that clients may retry.  The `command_id` is
+/// the key used to recognize a command that has already been handled, so it must stay the same across
+/// retries of the same command (a client supplied request id, or the id of the entity being created).
+pub trait IdempotentCommand: Command {
+    /// command_id returns the globally unique id of this command as a String.
+    fn command_id(&self) -> String;
+}
+
+/// DedupeStore is a collection like abstraction over storage of already handled commands.  It maps
+/// a command id to the result that was returned the first time the command was handled.
+///
+/// Like the `EventRepository`, the store takes in references and returns owned values.
+pub trait DedupeStore<R> {
+    /// Returns the stored result for the supplied command id, if that command was already handled.
+    fn get(&self, command_id: &String) -> Option<R>;
+
+    /// Returns a boolean indicating whether the command by the supplied id has already been handled.
+    fn contains_command(&self, command_id: &String) -> bool {
+        self.get(command_id).is_some()
+    }
+
+    /// Records the result of a handled command.  If the store did not have this command id present then
+    /// the result is returned.  If the command id was already present, the stored result is left untouched
+    /// and [`None`] is returned.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn insert(&mut self, command_id: &String, result: &R) -> Option<R>;
+}
+
+/// InMemoryDedupeStore is a `HashMap` backed implementation of `DedupeStore`.  It's useful for tests, and for
+/// single process applications where forgetting handled commands on restart is acceptable.
+pub struct InMemoryDedupeStore<R> {
+    results: HashMap<String, R>,
+}
+
+impl<R> InMemoryDedupeStore<R> {
+    pub fn new() -> InMemoryDedupeStore<R> {
+        InMemoryDedupeStore {
+            results: HashMap::new(),
+        }
+    }
+}
+
+impl<R> Default for InMemoryDedupeStore<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Clone> DedupeStore<R> for InMemoryDedupeStore<R> {
+    fn get(&self, command_id: &String) -> Option<R> {
+        self.results.get(command_id).cloned()
+    }
+
+    fn insert(&mut self, command_id: &String, result: &R) -> Option<R> {
+        if self.results.contains_key(command_id) {
+            return None;
+        }
+
+        self.results.insert(command_id.clone(), result.clone());
+        Some(result.clone())
+    }
+}
+
+/// IdempotentHandler wraps a command handler, and records the result of every successfully handled
+/// `IdempotentCommand` in a `DedupeStore`.  When a command with an already recorded id comes in again,
+/// the stored result is returned and the inner handler is not called a second time.
+///
+/// Only successful results are recorded.  A command that failed can be retried and will be handled again.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::command::*;
+/// use domain_patterns::message::Message;
+///
+/// struct CreateUserCommand {
+///     id: String,
+/// }
+///
+/// impl Command for CreateUserCommand {}
+/// impl Message for CreateUserCommand {}
+///
+/// impl IdempotentCommand for CreateUserCommand {
+///     fn command_id(&self) -> String {
+///         self.id.clone()
+///     }
+/// }
+///
+/// struct UserCommandsHandler {
+///     times_handled: usize,
+/// }
+///
+/// impl Handles<CreateUserCommand> for UserCommandsHandler {
+///     type Result = Result<String, String>;
+///
+///     fn handle(&mut self, msg: CreateUserCommand) -> Self::Result {
+///         self.times_handled += 1;
+///         Ok(msg.id)
+///     }
+/// }
+///
+/// let inner = UserCommandsHandler { times_handled: 0 };
+/// let mut handler = IdempotentHandler::new(inner, InMemoryDedupeStore::new());
+///
+/// handler.handle(CreateUserCommand { id: "1".to_string() }).unwrap();
+/// handler.handle(CreateUserCommand { id: "1".to_string() }).unwrap();
+///
+/// assert_eq!(handler.inner().times_handled, 1);
+/// ```
+pub struct IdempotentHandler<H, S> {
+    handler: H,
+    store: S,
+}
+
+impl<H, S> IdempotentHandler<H, S> {
+    pub fn new(handler: H, store: S) -> IdempotentHandler<H, S> {
+        IdempotentHandler {
+            handler,
+            store,
+        }
+    }
+
+    /// Returns a reference to the wrapped command handler.
+    pub fn inner(&self) -> &H {
+        &self.handler
+    }
+
+    /// Returns a reference to the underlying dedupe store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<C, H, S, T, E> Handles<C> for IdempotentHandler<H, S>
+    where C: IdempotentCommand,
+          H: Handles<C, Result = Result<T, E>>,
+          S: DedupeStore<T>,
+{
+    type Result = Result<T, E>;
+
+    fn handle(&mut self, msg: C) -> Self::Result {
+        let command_id = msg.command_id();
+        if let Some(result) = self.store.get(&command_id) {
+            return Ok(result);
+        }
+
+        let result = self.handler.handle(msg)?;
+        self.store.insert(&command_id, &result);
+
+        Ok(result)
+    }
+}
+
+/// CommandBus is the entry point commands are dispatched through.  It wraps a command handler, which following
+/// the pattern described on `Handles` is usually a single handler that handles every command of an aggregate.
+///
+/// Before a command is handed to the handler, the bus calls [`validate()`] on it.  If validation fails the
+/// handler is never called, and the `ValidationErrors` are converted into the handler's error type.
+///
+/// [`validate()`]: ./trait.Command.html#method.validate
+pub struct CommandBus<H> {
+    handler: H,
+}
+
+impl<H> CommandBus<H> {
+    pub fn new(handler: H) -> CommandBus<H> {
+        CommandBus {
+            handler,
+        }
+    }
+
+    /// Returns a reference to the wrapped command handler.
+    pub fn inner(&self) -> &H {
+        &self.handler
+    }
+
+    /// dispatch validates the command, and if it's valid passes it on to the wrapped handler.
+    pub fn dispatch<C, T, E>(&mut self, command: C) -> Result<T, E>
+        where C: Command,
+              H: Handles<C, Result = Result<T, E>>,
+              E: From<ValidationErrors>,
+    {
+        command.validate()?;
+        self.handler.handle(command)
+    }
+}
+
+impl<C, H, T, E> Handles<C> for CommandBus<H>
+    where C: Command,
+          H: Handles<C, Result = Result<T, E>>,
+          E: From<ValidationErrors>,
+{
+    type Result = Result<T, E>;
+
+    fn handle(&mut self, msg: C) -> Self::Result {
+        self.dispatch(msg)
+    }
+}
+
+/// AggregateCommand should be implemented by commands that target a single aggregate, so that generic
+/// handlers know which aggregate to load before handling the command.
+pub trait AggregateCommand: Command {
+    /// aggregate_id returns the id of the aggregate this command is issued against.
+    fn aggregate_id(&self) -> String;
+}
+
+/// DeciderHandler is the generic command handling glue for a `Decider`.  For every command it:
+///
+/// 1. Loads the decider's state, by folding every stored event for the command's aggregate id into `initial()`.
+/// 2. Calls `decide` with the command.
+/// 3. Appends the decided events to the event repository, expecting the aggregate to still be at the version it was loaded at.
+/// 4. Returns the appended events, so the caller can publish them.
+///
+/// If another writer appended events for the same aggregate between loading and appending, the `AppendError` from the
+/// event repository is converted into the decider's error type and nothing is appended.
+pub struct DeciderHandler<D, R> {
+    repo: R,
+    decider: PhantomData<D>,
+}
+
+impl<D, R> DeciderHandler<D, R>
+    where D: Decider,
+          R: EventRepository<Events = D::Events>,
+{
+    pub fn new(repo: R) -> DeciderHandler<D, R> {
+        DeciderHandler {
+            repo,
+            decider: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying event repository.
+    pub fn repo(&self) -> &R {
+        &self.repo
+    }
+
+    /// Rebuilds the decider's state for the supplied aggregate id, returning the state along with the
+    /// version the aggregate is at.
+    pub fn load(&self, aggregate_id: &String) -> (D, u64) {
+        let events = self.repo.events_since_version(aggregate_id, 0).unwrap_or_default();
+        let version = events.iter().map(|e| e.version()).max().unwrap_or(0);
+        let state = events.iter().fold(D::initial(), |state, event| state.evolve(event));
+
+        (state, version)
+    }
+}
+
+impl<D, R> Handles<D::Command> for DeciderHandler<D, R>
+    where D: Decider,
+          D::Command: AggregateCommand,
+          D::Error: From<AppendError>,
+          R: EventRepository<Events = D::Events>,
+{
+    type Result = Result<Vec<D::Events>, D::Error>;
+
+    fn handle(&mut self, msg: D::Command) -> Self::Result {
+        let aggregate_id = msg.aggregate_id();
+        let (state, version) = self.load(&aggregate_id);
+
+        let events = state.decide(msg)?;
+        self.repo.append(&aggregate_id, version, &events)?;
+
+        Ok(events)
+    }
+}
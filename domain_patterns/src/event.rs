@@ -1,5 +1,9 @@
 use crate::models::AggregateRoot;
 use crate::message::Message;
+use crate::command::Handles;
+use crate::time::{Timestamp, TimestampRangeError};
+use std::error::Error;
+use std::fmt;
 
 /// `DomainEvent` is a trait that defines an event relevant to the domain.  These are always facts about something
 /// that has already occurred that has domain significance.  An event has a time at which the event occurred,
@@ -7,9 +11,9 @@ use crate::message::Message;
 /// should also be sure to pass the aggregates `version` in when they create the event, so that
 /// events can be processed in the correct order.
 pub trait DomainEvent: Message {
-    /// occurred should return a timestamp as an i64.  You can generate this using a library like chrono
-    /// for the current time.
-    fn occurred(&self) -> i64;
+    /// occurred returns when the event happened, as a UTC `Timestamp` with millisecond precision.  Read the time
+    /// from a `Clock` from the time module when creating the event, so tests can control it with a `FixedClock`.
+    fn occurred(&self) -> Timestamp;
 
     /// id is the event's id, which should be automatically generated when constructing the implementor
     /// of DomainEvent trait.  Returned value of the getter should be a String. All that matters is
@@ -29,3 +33,173 @@ pub trait DomainEvent: Message {
     /// used to correctly order events for playback.
     fn version(&self) -> u64;
 }
+
+/// EventVariant lets a single domain event struct be pulled back out of the enum of events that holds it.  It's what
+/// allows subscribing to one variant of an event enum on the `EventBus`.
+///
+/// This is implemented for every variant automatically if you're using the `DomainEvents` macro from the domain_derive crate.
+pub trait EventVariant<E>: Sized {
+    /// from_event returns a reference to the inner event if the supplied event enum holds this variant,
+    /// and otherwise returns [`None`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn from_event(event: &E) -> Option<&Self>;
+}
+
+/// EventConstructorError is returned by the `new` constructor that `#[event(constructor)]` generates, when the
+/// id generator or the clock produce a value the event's fields can't hold.  `E` is the error of parsing the
+/// event's id type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventConstructorError<E> {
+    /// The id generator returned an id that can't be parsed into the event's id type.
+    Id(E),
+    /// The clock's time is out of range for the event's occurred type.
+    Occurred(TimestampRangeError),
+}
+
+impl<E: fmt::Display> fmt::Display for EventConstructorError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventConstructorError::Id(e) => write!(f, "the generated id is not a valid event id: {}", e),
+            EventConstructorError::Occurred(e) => write!(f, "the clock's time is not a valid event time: {}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for EventConstructorError<E> {}
+
+// A subscriber is stored as a closure that hands the event to the subscribed handler.
+type Subscriber<E, Err> = Box<dyn FnMut(&E) -> Result<(), Err>>;
+
+/// PublishPolicy decides what the `EventBus` does when a subscriber returns an error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublishPolicy {
+    /// Stop publishing as soon as one subscriber fails.  Subscribers registered after the failing one do not
+    /// see the event.
+    StopOnFirstError,
+    /// Keep publishing to every subscriber, and report all errors that occurred once every subscriber has run.
+    ContinueOnError,
+}
+
+/// EventBus is an in-process, synchronous publisher of domain events.  Subscribers are any `Handles` implementation,
+/// and can subscribe either to the whole event enum, or to a single variant of it.
+///
+/// Events are published to subscribers in the order that subscribers were registered.  Each subscriber receives an owned
+/// clone of the event, since `Handles` takes ownership of the messages it handles.  The `Ok` value a subscriber returns
+/// is ignored, but errors are collected according to the bus' `PublishPolicy`.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::command::Handles;
+/// use domain_patterns::event::{EventBus, PublishPolicy};
+/// # use domain_patterns::event::DomainEvent;
+/// # use domain_patterns::message::Message;
+/// #
+/// # #[derive(Clone)]
+/// # struct UserCreatedEvent;
+/// # impl Message for UserCreatedEvent {}
+/// # impl DomainEvent for UserCreatedEvent {
+/// #     fn occurred(&self) -> domain_patterns::time::Timestamp { 0.into() }
+/// #     fn id(&self) -> String { "1".to_string() }
+/// #     fn aggregate_id(&self) -> String { "1".to_string() }
+/// #     fn version(&self) -> u64 { 1 }
+/// # }
+///
+/// struct WelcomeEmailSender;
+///
+/// impl Handles<UserCreatedEvent> for WelcomeEmailSender {
+///     type Result = Result<(), String>;
+///
+///     fn handle(&mut self, msg: UserCreatedEvent) -> Self::Result {
+///         // send the welcome email here.
+///         Ok(())
+///     }
+/// }
+///
+/// let mut bus = EventBus::new(PublishPolicy::ContinueOnError);
+/// bus.subscribe(WelcomeEmailSender);
+///
+/// assert!(bus.publish(&UserCreatedEvent).is_ok());
+/// ```
+pub struct EventBus<E, Err> {
+    subscribers: Vec<Subscriber<E, Err>>,
+    policy: PublishPolicy,
+}
+
+impl<E, Err> EventBus<E, Err>
+    where E: DomainEvent + Clone + 'static,
+          Err: 'static,
+{
+    pub fn new(policy: PublishPolicy) -> EventBus<E, Err> {
+        EventBus {
+            subscribers: Vec::new(),
+            policy,
+        }
+    }
+
+    /// Subscribes a handler to every event published on the bus.
+    pub fn subscribe<H, T>(&mut self, mut handler: H)
+        where H: Handles<E, Result = Result<T, Err>> + 'static,
+    {
+        self.subscribers.push(Box::new(move |event: &E| {
+            handler.handle(event.clone()).map(|_| ())
+        }));
+    }
+
+    /// Subscribes a handler to a single variant of the event enum.  Events holding any other variant are skipped
+    /// for this subscriber.
+    pub fn subscribe_to<V, H, T>(&mut self, mut handler: H)
+        where V: EventVariant<E> + Message + Clone,
+              H: Handles<V, Result = Result<T, Err>> + 'static,
+    {
+        self.subscribers.push(Box::new(move |event: &E| {
+            match V::from_event(event) {
+                Some(variant) => handler.handle(variant.clone()).map(|_| ()),
+                None => Ok(()),
+            }
+        }));
+    }
+
+    /// Returns the number of registered subscribers.
+    pub fn num_subscribers(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Publishes a single event to all subscribers, in registration order.
+    ///
+    /// # Failure case
+    ///
+    /// If any subscriber fails, the errors are returned in the order they occurred.  With `StopOnFirstError`
+    /// this is always a single error.
+    pub fn publish(&mut self, event: &E) -> Result<(), Vec<Err>> {
+        self.publish_all(std::slice::from_ref(event))
+    }
+
+    /// Publishes each event in turn to all subscribers.  This is handy for publishing every event that came out of
+    /// handling a single command.
+    ///
+    /// # Failure case
+    ///
+    /// If any subscriber fails, the errors are returned in the order they occurred.  With `StopOnFirstError`
+    /// publishing stops at the first failure, so later events are not published at all.
+    pub fn publish_all(&mut self, events: &[E]) -> Result<(), Vec<Err>> {
+        let mut errors = Vec::new();
+
+        for event in events {
+            for subscriber in self.subscribers.iter_mut() {
+                if let Err(e) = subscriber(event) {
+                    errors.push(e);
+                    if self.policy == PublishPolicy::StopOnFirstError {
+                        return Err(errors);
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(errors)
+    }
+}
@@ -45,18 +45,52 @@
 /// traits that define characteristics of entities and value objects.
 pub mod models;
 
+/// Values holds common, validated value objects such as `Email`, `PhoneNumber`, `Percentage` and `Money`.  It is only
+/// available with the `values` feature enabled.
+#[cfg(feature = "values")]
+pub mod values;
+
 /// Collections holds traits that define collection like abstractions. Currently it contains collection like abstractions over
 /// database accesss in the form of the `Repository` pattern.
 pub mod collections;
 
+/// Spec module holds `QuerySpec`, a backend neutral description of the filters, sorting and limit of a query over
+/// read models, which can be evaluated in memory or translated into SQL.
+pub mod spec;
+
 /// Event module holds the event trait that defines characteristics of all domain events.
 pub mod event;
 
 /// Command module holds traits relevant to marking commands, as well as command handler traits.
 pub mod command;
 
-/// Query module holds traits relevant to representing query handlers in a CQRS architecture.
+/// Query module holds traits relevant to representing query handlers in a CQRS architecture, as well as a query dispatcher
+/// that caches query results.
 pub mod query;
 
 /// Message module holds a single marker trait that is shared by both commands and events, so command handlers can handle both commands and events.
 pub mod message;
+
+/// Process module holds the process manager (saga) trait used to coordinate workflows that span multiple aggregates,
+/// along with a scheduler for delivering commands and timeouts at a later time.
+pub mod process;
+
+/// Time module holds the `Clock` abstraction, so that code which needs the current time can be tested deterministically,
+/// and the `Timestamp` type for points in time.
+pub mod time;
+
+/// Strategy holds proptest strategies for property based tests over value objects and events, and is used by the
+/// code generated with `#[value_object(arbitrary)]` and `#[event(arbitrary)]`.  It is only available with the
+/// `proptest` feature enabled.
+#[cfg(feature = "proptest")]
+pub mod strategy;
+
+/// Testing holds a given/when/then harness for testing deciders, aggregates and command handlers, which asserts on
+/// the events they raise, along with conformance suites that check a repository implementation against the documented contract of
+/// its trait.
+pub mod testing;
+
+/// regex is re-exported so the code generated by `#[value_object(regex = "..")]` doesn't need your crate to depend on
+/// it directly.  It is only available with the `regex` or `values` feature enabled.
+#[cfg(feature = "regex")]
+pub use regex;
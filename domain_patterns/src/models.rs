@@ -1,12 +1,24 @@
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::{error, fmt};
 use crate::event::DomainEvent;
+use crate::command::Command;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
 /// A trait that defines an `Entity`, which is any object with a unique and globally persistent identity.
 ///
 /// The generic type `K` should match the same type as the internal globally unique id used for the entity.
 /// Be careful when choosing what to return here.  The result of [`id()`] will be used as the primary key
-/// for the entity when communicating with a database via a repository.
+/// for the entity when communicating with a database via a repository.  Holding the id as an `Id<Self>` keeps
+/// ids of different entities from being mixed up.
 ///
 /// # Example
 /// ```rust
@@ -38,6 +50,312 @@ pub trait Entity: PartialEq {
     fn id(&self) -> String;
 }
 
+/// Id is a strongly typed identifier for an entity of type `T`.  An `Id<User>` and an `Id<Order>` are different
+/// types, so they can't be swapped by mistake.  The id is either a UUID or a ULID, held in its canonical text form:
+/// lowercase and hyphenated for a UUID, and uppercase for a ULID.
+///
+/// Ids are generated with `Id::new_v4` and `Id::new_ulid`, which need the `uuid` and `ulid` features respectively,
+/// and parsed with `FromStr` or `TryFrom<String>`.  They serialize as a string, and deserializing validates it.
+///
+/// An `Id` dereferences to its `String`, so it can be passed straight to repository methods, and the `Entity`
+/// and `DomainEvent` derive macros accept `Id` fields.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::models::Id;
+///
+/// struct User;
+///
+/// let id: Id<User> = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+/// assert_eq!(id.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// assert!(id.is_ulid());
+///
+/// assert!("not an id".parse::<Id<User>>().is_err());
+/// ```
+pub struct Id<T> {
+    value: String,
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    fn from_canonical(value: String) -> Id<T> {
+        Id {
+            value,
+            entity: PhantomData,
+        }
+    }
+
+    /// Generates a random version 4 UUID.
+    #[cfg(feature = "uuid")]
+    pub fn new_v4() -> Id<T> {
+        Id::from_canonical(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Generates a ULID, which sorts by the time it was generated.
+    #[cfg(feature = "ulid")]
+    pub fn new_ulid() -> Id<T> {
+        Id::from_canonical(ulid::Ulid::new().to_string())
+    }
+
+    /// Returns the id as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns true if the id is a UUID.
+    pub fn is_uuid(&self) -> bool {
+        self.value.len() == 36
+    }
+
+    /// Returns true if the id is a ULID.
+    pub fn is_ulid(&self) -> bool {
+        self.value.len() == 26
+    }
+
+    // Returns the canonical form of a UUID or ULID, or None if the value is neither.
+    fn canonicalize(value: &str) -> Option<String> {
+        let is_uuid = value.len() == 36 && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+        if is_uuid {
+            return Some(value.to_ascii_lowercase());
+        }
+
+        // ULIDs use Crockford's base32, and the first character can be at most 7 for the timestamp to fit in 48 bits.
+        let ulid = value.to_ascii_uppercase();
+        let is_ulid = ulid.len() == 26
+            && ulid.starts_with(|c: char| ('0'..='7').contains(&c))
+            && ulid.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() && !"ILOU".contains(c));
+        if is_ulid {
+            return Some(ulid);
+        }
+
+        None
+    }
+}
+
+impl<T> TryFrom<String> for Id<T> {
+    type Error = ValidationErrors;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+
+        let canonical = Self::canonicalize(&value).unwrap_or(value);
+        Ok(Id::from_canonical(canonical))
+    }
+}
+
+impl<T> FromStr for Id<T> {
+    type Err = ValidationErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Id::try_from(s.to_string())
+    }
+}
+
+impl<T> ValueObject<String> for Id<T> {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        if Self::canonicalize(value).is_none() {
+            return Err(ValidationErrors::single("", "id", "must be a UUID or a ULID"));
+        }
+
+        Ok(())
+    }
+
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+impl<T> FieldValidation<String> for Id<T> {
+    fn code() -> &'static str {
+        "id"
+    }
+
+    fn validate_field(path: &str, value: &String) -> Result<(), ValidationErrors> {
+        Self::validate(value).map_err(|e| e.nest(path))
+    }
+}
+
+impl<T> Deref for Id<T> {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.value
+    }
+}
+
+impl<T> AsRef<str> for Id<T> {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<T> Borrow<str> for Id<T> {
+    fn borrow(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<T> From<Id<T>> for String {
+    fn from(id: Id<T>) -> String {
+        id.value
+    }
+}
+
+impl<T> fmt::Display for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Id({})", self.value)
+    }
+}
+
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        Id::from_canonical(self.value.clone())
+    }
+}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<T> Serialize for Id<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Id<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Id::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// IdGenerator is an abstraction over generating ids.  Code that creates entities or events should take an
+/// `IdGenerator` rather than generating random ids directly, so tests can use predictable ids.
+///
+/// Ids are returned as strings, and must be valid UUIDs, so they can be parsed into a `Uuid`, an `Id<T>` or a
+/// `String`.  The `DomainEvent` derive macro relies on this when generating constructors.
+pub trait IdGenerator {
+    /// next_id returns a new id.
+    fn next_id(&self) -> String;
+}
+
+impl<G: IdGenerator + ?Sized> IdGenerator for &G {
+    fn next_id(&self) -> String {
+        (**self).next_id()
+    }
+}
+
+impl<G: IdGenerator + ?Sized> IdGenerator for Rc<G> {
+    fn next_id(&self) -> String {
+        (**self).next_id()
+    }
+}
+
+/// UuidGenerator generates random version 4 UUIDs.  It needs the `uuid` feature.
+#[cfg(feature = "uuid")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidGenerator;
+
+#[cfg(feature = "uuid")]
+impl IdGenerator for UuidGenerator {
+    fn next_id(&self) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+/// FixedIdGenerator always returns the same id.  It's meant for tests.
+#[derive(Debug, Clone)]
+pub struct FixedIdGenerator {
+    id: String,
+}
+
+impl FixedIdGenerator {
+    /// Creates a generator that always returns the supplied id, which should be a valid UUID.
+    pub fn new(id: &str) -> FixedIdGenerator {
+        FixedIdGenerator {
+            id: id.to_string(),
+        }
+    }
+}
+
+impl IdGenerator for FixedIdGenerator {
+    fn next_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+/// SequentialIdGenerator returns UUIDs counting up from `00000000-0000-0000-0000-000000000001`.  It's meant for
+/// tests, where it makes ids predictable while keeping them unique.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::models::{IdGenerator, SequentialIdGenerator};
+///
+/// let ids = SequentialIdGenerator::new();
+/// assert_eq!(ids.next_id(), "00000000-0000-0000-0000-000000000001");
+/// assert_eq!(ids.next_id(), "00000000-0000-0000-0000-000000000002");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SequentialIdGenerator {
+    last: Cell<u128>,
+}
+
+impl SequentialIdGenerator {
+    pub fn new() -> SequentialIdGenerator {
+        SequentialIdGenerator::default()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> String {
+        let next = self.last.get() + 1;
+        self.last.set(next);
+
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            next >> 96,
+            (next >> 80) & 0xffff,
+            (next >> 64) & 0xffff,
+            (next >> 48) & 0xffff,
+            next & 0xffff_ffff_ffff,
+        )
+    }
+}
+
 pub trait AggregateRoot: Entity {
     /// This type alias should point to an enum of events that the aggregate root will create and publish.
     type Events: DomainEvent;
@@ -71,8 +389,40 @@ pub trait Applier: AggregateRoot {
     fn apply(&mut self, event: Self::Events) -> Result<(), Self::EventError>;
 }
 
-// TODO: Improve error handling situation for ValueObjects.  Maybe validate should return a list of errors rather
-// than a boolean?
+/// Decider is a functional alternative to `AggregateRoot` plus `Applier`.  The implementor is the state of
+/// a single aggregate, and both halves of the aggregate's job are pure functions on that state:
+///
+/// 1. `decide` looks at the current state and a command, and either says "no" with an error, or returns
+///    the events that record what happened.  It never mutates state.
+/// 2. `evolve` folds a single event into the state, producing the next state.
+///
+/// Current state is rebuilt by folding every stored event into `initial()`.  Because the decider does not
+/// know which version it's at unless it tracks it, events created in `decide` should carry a version taken from
+/// state that `evolve` keeps up to date (the version of the last event seen, plus one).
+///
+/// The `DeciderHandler` in the command module is the glue that loads state from an `EventRepository`, calls
+/// `decide`, and appends the resulting events.
+pub trait Decider: Sized {
+    /// Command is the command (or enum of commands) this decider handles.
+    type Command: Command;
+
+    /// This type alias should point to an enum of events that the decider creates.
+    type Events: DomainEvent;
+
+    /// This type alias should point to the root error type for the crate.
+    type Error;
+
+    /// initial returns the state of the aggregate before any events have occurred.
+    fn initial() -> Self;
+
+    /// decide takes in a command and returns the events that should be recorded as a result of the
+    /// command, or an error if the command is rejected given the current state.
+    fn decide(&self, command: Self::Command) -> Result<Vec<Self::Events>, Self::Error>;
+
+    /// evolve applies a single event to the state, returning the new state.
+    fn evolve(self, event: &Self::Events) -> Self;
+}
+
 /// A trait that defines a `ValueObject` which is an immutable holder of value, that validates that value
 /// against certain conditions before storing it.
 ///
@@ -144,6 +494,9 @@ pub trait Applier: AggregateRoot {
 ///
 /// let email = Email::try_from("test_email@email.com".to_string()).unwrap();
 /// ```
+///
+/// `validate` returns a single error.  To validate several value objects at once and report every failure,
+/// implement `FieldValidation` as well and combine the results with `ValidateAll`.
 pub trait ValueObject<T>: Clone + PartialEq + TryFrom<T> + Display {
     /// ValueError defines an error type that communicates there was a problem with validation.
     type ValueError;
@@ -164,3 +517,276 @@ pub trait ValueObject<T>: Clone + PartialEq + TryFrom<T> + Display {
     /// back a ref of it).
     fn value(&self) -> T;
 }
+
+/// ValidationError describes a single validation failure.  The `path` points at the offending field, using dots
+/// for nested fields and brackets for list items, such as `address.zip` or `emails[2]`.  The `code` is a short
+/// machine readable name for the rule that failed, such as `non_empty` or `invalid_email`, and the `message` is
+/// meant for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationError {
+    pub path: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(path: &str, code: &str, message: &str) -> ValidationError {
+        ValidationError {
+            path: path.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            return write!(f, "{}", self.message);
+        }
+
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// ValidationErrors collects every `ValidationError` found while validating some input, so the caller can report
+/// all invalid fields at once instead of one at a time.  It derives serde's `Serialize`, so it can be returned
+/// as-is from a REST controller.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::models::ValidationErrors;
+///
+/// let mut address = ValidationErrors::new();
+/// address.add("zip", "invalid_zip", "is not a valid zip code");
+///
+/// let mut errors = ValidationErrors::new();
+/// errors.add("name", "non_empty", "must not be empty");
+/// errors.merge(address.nest("address"));
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors.for_path("address.zip")[0].code, "invalid_zip");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ValidationErrors {
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> ValidationErrors {
+        ValidationErrors::default()
+    }
+
+    /// Creates a collection holding a single error.
+    pub fn single(path: &str, code: &str, message: &str) -> ValidationErrors {
+        ValidationErrors {
+            errors: vec![ValidationError::new(path, code, message)],
+        }
+    }
+
+    /// Records an error at the supplied path.
+    pub fn add(&mut self, path: &str, code: &str, message: &str) {
+        self.errors.push(ValidationError::new(path, code, message));
+    }
+
+    /// Moves every error from `other` into this collection.
+    pub fn merge(&mut self, other: ValidationErrors) {
+        self.errors.extend(other.errors);
+    }
+
+    /// Prefixes the path of every error with `prefix`, which is how errors from validating a nested object are
+    /// reported against the field that holds it.
+    pub fn nest(self, prefix: &str) -> ValidationErrors {
+        if prefix.is_empty() {
+            return self;
+        }
+
+        let errors = self.errors.into_iter()
+            .map(|mut e| {
+                e.path = match e.path.as_str() {
+                    "" => prefix.to_string(),
+                    path if path.starts_with('[') => format!("{}{}", prefix, path),
+                    path => format!("{}.{}", prefix, path),
+                };
+                e
+            })
+            .collect();
+
+        ValidationErrors { errors }
+    }
+
+    /// Records the errors of a failed result and returns [`None`], or returns the value of a successful one.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    pub fn check<V>(&mut self, result: Result<V, ValidationErrors>) -> Option<V> {
+        match result {
+            Ok(value) => Some(value),
+            Err(errors) => {
+                self.merge(errors);
+                None
+            },
+        }
+    }
+
+    /// Returns every error recorded at exactly the supplied path.
+    pub fn for_path(&self, path: &str) -> Vec<&ValidationError> {
+        self.errors.iter().filter(|e| e.path == path).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ValidationError> {
+        self.errors.iter()
+    }
+
+    /// Returns `Ok` if no errors were recorded, and otherwise returns the collection as an error.
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        Err(self)
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(|e| e.to_string()).collect();
+        write!(f, "validation failed: {}", errors.join(", "))
+    }
+}
+
+impl error::Error for ValidationErrors {}
+
+impl<'a> IntoIterator for &'a ValidationErrors {
+    type Item = &'a ValidationError;
+    type IntoIter = std::slice::Iter<'a, ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+/// FieldValidation extends a `ValueObject` so its validation failure can be reported as `ValidationErrors` at a
+/// field path, with a machine readable error code.  The human readable message is the `Display` output of the
+/// value object's `ValueError`.
+///
+/// The `ValueSetup` derive macro from `domain_derive` implements this trait, using `invalid_` followed by the
+/// snake cased name of the struct as the code, unless a code is given with `#[value_object(code = "..")]`.
+pub trait FieldValidation<T>: ValueObject<T> {
+    /// code returns the error code reported when validation fails.
+    fn code() -> &'static str {
+        "invalid"
+    }
+
+    /// Validates the value, reporting a failure at the supplied path.
+    fn validate_field(path: &str, value: &T) -> Result<(), ValidationErrors>
+        where Self::ValueError: Display,
+    {
+        Self::validate(value).map_err(|e| ValidationErrors::single(path, Self::code(), &e.to_string()))
+    }
+
+    /// Validates the value and constructs the value object from it, reporting a failure at the supplied path.
+    fn try_from_field(path: &str, value: T) -> Result<Self, ValidationErrors>
+        where Self::ValueError: Display,
+    {
+        Self::validate_field(path, &value)?;
+        Self::try_from(value).map_err(|_| ValidationErrors::single(path, Self::code(), "could not be constructed"))
+    }
+}
+
+/// ValidateAll is implemented for tuples of results that fail with `ValidationErrors`.  It succeeds with a tuple
+/// of every value if all results succeeded, and otherwise fails with the errors of every failed result merged
+/// together.
+///
+/// # Example
+/// ```rust
+/// # use std::convert::TryFrom;
+/// # use std::fmt;
+/// use domain_patterns::models::{ValueObject, FieldValidation, ValidateAll};
+///
+/// # #[derive(Debug, Clone, PartialEq)]
+/// struct Name {
+///     value: String,
+/// }
+///
+/// # impl fmt::Display for Name {
+/// #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.value) }
+/// # }
+/// # impl TryFrom<String> for Name {
+/// #     type Error = String;
+/// #     fn try_from(value: String) -> Result<Self, Self::Error> {
+/// #         Self::validate(&value)?;
+/// #         Ok(Name { value })
+/// #     }
+/// # }
+/// impl ValueObject<String> for Name {
+///     type ValueError = String;
+///
+///     fn validate(value: &String) -> Result<(), String> {
+///         if value.is_empty() {
+///             return Err("must not be empty".to_string());
+///         }
+///         Ok(())
+///     }
+///
+///     fn value(&self) -> String {
+///         self.value.clone()
+///     }
+/// }
+///
+/// impl FieldValidation<String> for Name {
+///     fn code() -> &'static str {
+///         "invalid_name"
+///     }
+/// }
+///
+/// let errors = (
+///     Name::try_from_field("first_name", "".to_string()),
+///     Name::try_from_field("last_name", "".to_string()),
+/// ).validate_all().unwrap_err();
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors.to_string(), "validation failed: first_name: must not be empty, last_name: must not be empty");
+/// ```
+pub trait ValidateAll {
+    /// Output is the tuple of validated values.
+    type Output;
+
+    fn validate_all(self) -> Result<Self::Output, ValidationErrors>;
+}
+
+macro_rules! impl_validate_all {
+    ($($value:ident),+) => {
+        impl<$($value),+> ValidateAll for ($(Result<$value, ValidationErrors>,)+) {
+            type Output = ($($value,)+);
+
+            #[allow(non_snake_case)]
+            fn validate_all(self) -> Result<Self::Output, ValidationErrors> {
+                let mut errors = ValidationErrors::new();
+                let ($($value,)+) = self;
+                $(let $value = errors.check($value);)+
+
+                match ($($value,)+) {
+                    ($(Some($value),)+) => Ok(($($value,)+)),
+                    _ => Err(errors),
+                }
+            }
+        }
+    };
+}
+
+impl_validate_all!(A);
+impl_validate_all!(A, B);
+impl_validate_all!(A, B, C);
+impl_validate_all!(A, B, C, D);
+impl_validate_all!(A, B, C, D, E);
+impl_validate_all!(A, B, C, D, E, F);
+impl_validate_all!(A, B, C, D, E, F, G);
+impl_validate_all!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,356 @@
+use crate::command::{Command, Handles};
+use crate::event::DomainEvent;
+use crate::message::Message;
+use crate::time::{Clock, Timestamp};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// ProcessManager coordinates a long running workflow that spans multiple aggregates, such as creating a user
+/// and then provisioning an account for them.  A process manager listens to domain events, and in reaction issues
+/// commands.  It's also known as a saga.
+///
+/// Each running instance of the process is identified by a correlation id, which is usually the id of the aggregate
+/// that started the process.  Every instance keeps its own `State`, which is persisted in a `ProcessStateStore`
+/// between events.
+///
+/// A process manager is driven by a `ProcessRunner`, which loads state, calls `react`, dispatches the resulting
+/// commands and then saves state.
+pub trait ProcessManager {
+    /// This type alias should point to an enum of events the process manager listens to.
+    type Events: DomainEvent;
+
+    /// This type alias should point to an enum of commands the process manager issues.  Commands need to be
+    /// clonable so the failed command can be handed to `compensate` after it was handed to a handler.
+    type Commands: Command + Clone;
+
+    /// State is the persisted state of a single process instance.
+    type State: Clone;
+
+    /// This type alias should point to the root error type for the crate.
+    type Error;
+
+    /// correlation_id returns the id of the process instance the event belongs to, or [`None`] if the process
+    /// manager is not interested in the event.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn correlation_id(&self, event: &Self::Events) -> Option<String>;
+
+    /// initial_state returns the state of a process instance that has not seen any events yet.
+    fn initial_state(&self) -> Self::State;
+
+    /// react updates the state of the process instance with the incoming event, and returns the commands that
+    /// should be issued as a result, in the order they should be dispatched.
+    fn react(&self, state: &mut Self::State, event: &Self::Events) -> Result<Vec<Self::Commands>, Self::Error>;
+
+    /// compensate returns the commands that undo the work already done by the process, after the supplied command failed.
+    /// For example, if provisioning an account fails after the user was created, the compensation could be to
+    /// deactivate the user.  The default implementation has no compensation steps.
+    fn compensate(&self, _state: &Self::State, _failed: &Self::Commands) -> Vec<Self::Commands> {
+        Vec::new()
+    }
+
+    /// is_complete returns true once the process instance has finished, at which point its state is removed
+    /// from the store.  The default implementation never completes.
+    fn is_complete(&self, _state: &Self::State) -> bool {
+        false
+    }
+}
+
+/// ProcessStateStore is a collection like abstraction over storage of process manager state, keyed by correlation id.
+pub trait ProcessStateStore<S> {
+    /// Returns the state stored for the supplied correlation id as an owned type.
+    fn get(&self, correlation_id: &String) -> Option<S>;
+
+    /// Stores the state at the supplied correlation id, replacing any state that was there before.
+    fn save(&mut self, correlation_id: &String, state: &S);
+
+    /// Removes the state at the supplied correlation id, returning it if it was present.
+    fn remove(&mut self, correlation_id: &String) -> Option<S>;
+}
+
+/// InMemoryProcessStateStore is a `HashMap` backed implementation of `ProcessStateStore`.
+pub struct InMemoryProcessStateStore<S> {
+    states: HashMap<String, S>,
+}
+
+impl<S> InMemoryProcessStateStore<S> {
+    pub fn new() -> InMemoryProcessStateStore<S> {
+        InMemoryProcessStateStore {
+            states: HashMap::new(),
+        }
+    }
+}
+
+impl<S> Default for InMemoryProcessStateStore<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Clone> ProcessStateStore<S> for InMemoryProcessStateStore<S> {
+    fn get(&self, correlation_id: &String) -> Option<S> {
+        self.states.get(correlation_id).cloned()
+    }
+
+    fn save(&mut self, correlation_id: &String, state: &S) {
+        self.states.insert(correlation_id.clone(), state.clone());
+    }
+
+    fn remove(&mut self, correlation_id: &String) -> Option<S> {
+        self.states.remove(correlation_id)
+    }
+}
+
+/// ProcessRunner drives a `ProcessManager`.  It implements `Handles` for the process manager's events, so it can
+/// be subscribed to an `EventBus`.  For every event it:
+///
+/// 1. Asks the process manager for the correlation id, and ignores the event if there is none.
+/// 2. Loads the process instance's state from the store, or starts from `initial_state`.
+/// 3. Calls `react` to update the state.
+/// 4. Dispatches the commands to the command handler (usually a `CommandBus`) in order.
+/// 5. Saves the updated state, or removes it if the process instance is complete.
+///
+/// If a command fails, the remaining commands are not dispatched.  Instead the compensation commands returned by
+/// `compensate` are dispatched, and the error of the failed command is returned.  Compensation is best effort, so
+/// errors from compensation commands do not replace the original error.  The updated state is not saved when a
+/// command fails, so a redelivered event reacts from the state the process instance had before the event.
+pub struct ProcessRunner<P, S, H> {
+    process: P,
+    store: S,
+    commands: H,
+}
+
+impl<P, S, H> ProcessRunner<P, S, H>
+    where P: ProcessManager,
+          S: ProcessStateStore<P::State>,
+{
+    pub fn new(process: P, store: S, commands: H) -> ProcessRunner<P, S, H> {
+        ProcessRunner {
+            process,
+            store,
+            commands,
+        }
+    }
+
+    /// Returns the current state of the process instance by the supplied correlation id.
+    pub fn state(&self, correlation_id: &String) -> Option<P::State> {
+        self.store.get(correlation_id)
+    }
+
+    /// Returns a reference to the command handler commands are dispatched to.
+    pub fn commands(&self) -> &H {
+        &self.commands
+    }
+}
+
+impl<P, S, H, T> Handles<P::Events> for ProcessRunner<P, S, H>
+    where P: ProcessManager,
+          S: ProcessStateStore<P::State>,
+          H: Handles<P::Commands, Result = Result<T, P::Error>>,
+{
+    type Result = Result<(), P::Error>;
+
+    fn handle(&mut self, msg: P::Events) -> Self::Result {
+        let correlation_id = match self.process.correlation_id(&msg) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let mut state = self.store.get(&correlation_id).unwrap_or_else(|| self.process.initial_state());
+        let commands = self.process.react(&mut state, &msg)?;
+
+        for command in commands {
+            if let Err(e) = self.commands.handle(command.clone()) {
+                for compensation in self.process.compensate(&state, &command) {
+                    let _ = self.commands.handle(compensation);
+                }
+                return Err(e);
+            }
+        }
+
+        if self.process.is_complete(&state) {
+            self.store.remove(&correlation_id);
+        } else {
+            self.store.save(&correlation_id, &state);
+        }
+
+        Ok(())
+    }
+}
+
+/// ScheduledMessage is a message, usually a command or a timeout, that should be delivered at a later time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledMessage<M> {
+    /// id uniquely identifies the scheduled message, so it can be cancelled.  For a saga timeout, the correlation
+    /// id of the process instance plus the name of the timeout makes a good id.
+    pub id: String,
+    /// due is the time at which the message should be delivered.
+    pub due: Timestamp,
+    pub message: M,
+}
+
+/// ScheduleStore is a collection like abstraction over persisted scheduled messages.
+pub trait ScheduleStore<M> {
+    /// Inserts a scheduled message.  If the store did not have this id present, then the scheduled message is returned.
+    /// If the id is already in use, then nothing is inserted and [`None`] is returned.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn insert(&mut self, scheduled: &ScheduledMessage<M>) -> Option<ScheduledMessage<M>>;
+
+    /// Removes the scheduled message by the supplied id, returning it if it was present.
+    fn remove(&mut self, id: &String) -> Option<ScheduledMessage<M>>;
+
+    /// Returns every scheduled message that is due at or before `now`, ordered by due time.
+    fn due(&self, now: Timestamp) -> Vec<ScheduledMessage<M>>;
+}
+
+/// InMemoryScheduleStore is a `HashMap` backed implementation of `ScheduleStore`.
+pub struct InMemoryScheduleStore<M> {
+    scheduled: HashMap<String, ScheduledMessage<M>>,
+}
+
+impl<M> InMemoryScheduleStore<M> {
+    pub fn new() -> InMemoryScheduleStore<M> {
+        InMemoryScheduleStore {
+            scheduled: HashMap::new(),
+        }
+    }
+}
+
+impl<M> Default for InMemoryScheduleStore<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Clone> ScheduleStore<M> for InMemoryScheduleStore<M> {
+    fn insert(&mut self, scheduled: &ScheduledMessage<M>) -> Option<ScheduledMessage<M>> {
+        if self.scheduled.contains_key(&scheduled.id) {
+            return None;
+        }
+
+        self.scheduled.insert(scheduled.id.clone(), scheduled.clone());
+        Some(scheduled.clone())
+    }
+
+    fn remove(&mut self, id: &String) -> Option<ScheduledMessage<M>> {
+        self.scheduled.remove(id)
+    }
+
+    fn due(&self, now: Timestamp) -> Vec<ScheduledMessage<M>> {
+        let mut due: Vec<ScheduledMessage<M>> = self.scheduled
+            .values()
+            .filter(|s| s.due <= now)
+            .cloned()
+            .collect();
+
+        due.sort_by_key(|s| s.due);
+        due
+    }
+}
+
+/// Scheduler defers messages until they come due, which is what process managers need for timeouts such as
+/// "if no payment was received within 30 minutes, cancel the order".  Scheduled messages are kept in a
+/// `ScheduleStore`, so they survive restarts if the store is persistent.
+///
+/// The scheduler doesn't run in the background.  Call `deliver_due` periodically (or from a test, after advancing
+/// a `FixedClock`) to hand every message that has come due to a handler.
+///
+/// # Example
+/// ```rust
+/// use std::rc::Rc;
+/// use std::time::Duration;
+/// use domain_patterns::command::{Command, Handles};
+/// use domain_patterns::message::Message;
+/// use domain_patterns::process::{Scheduler, InMemoryScheduleStore};
+/// use domain_patterns::time::{FixedClock, Timestamp};
+///
+/// #[derive(Clone)]
+/// struct CancelOrderCommand {
+///     order_id: String,
+/// }
+///
+/// impl Command for CancelOrderCommand {}
+/// impl Message for CancelOrderCommand {}
+///
+/// struct OrderCommandsHandler;
+///
+/// impl Handles<CancelOrderCommand> for OrderCommandsHandler {
+///     type Result = String;
+///
+///     fn handle(&mut self, msg: CancelOrderCommand) -> Self::Result {
+///         msg.order_id
+///     }
+/// }
+///
+/// let clock = Rc::new(FixedClock::new(Timestamp::from_millis(0)));
+/// let mut scheduler = Scheduler::new(InMemoryScheduleStore::new(), clock.clone());
+/// let thirty_minutes = Duration::from_secs(30 * 60);
+/// scheduler.schedule_in("order-1-payment-timeout", thirty_minutes, CancelOrderCommand { order_id: "order-1".to_string() });
+///
+/// let mut handler = OrderCommandsHandler;
+/// assert!(scheduler.deliver_due(&mut handler).is_empty());
+///
+/// clock.advance(thirty_minutes);
+/// assert_eq!(scheduler.deliver_due(&mut handler), vec!["order-1".to_string()]);
+/// ```
+pub struct Scheduler<M, S, C> {
+    store: S,
+    clock: C,
+    message: PhantomData<M>,
+}
+
+impl<M, S, C> Scheduler<M, S, C>
+    where M: Message,
+          S: ScheduleStore<M>,
+          C: Clock,
+{
+    pub fn new(store: S, clock: C) -> Scheduler<M, S, C> {
+        Scheduler {
+            store,
+            clock,
+            message: PhantomData,
+        }
+    }
+
+    /// Schedules the message for delivery at the supplied time.  Returns [`None`] if a message with the same id is
+    /// already scheduled.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    pub fn schedule_at(&mut self, id: &str, due: Timestamp, message: M) -> Option<ScheduledMessage<M>> {
+        self.store.insert(&ScheduledMessage {
+            id: id.to_string(),
+            due,
+            message,
+        })
+    }
+
+    /// Schedules the message for delivery once the supplied delay has passed.  A delay too large to add to the
+    /// current time, such as `Duration::MAX`, schedules the message for the latest timestamp.  Returns [`None`] if a
+    /// message with the same id is already scheduled.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    pub fn schedule_in(&mut self, id: &str, delay: Duration, message: M) -> Option<ScheduledMessage<M>> {
+        let due = self.clock.now().saturating_add(delay);
+        self.schedule_at(id, due, message)
+    }
+
+    /// Cancels the scheduled message by the supplied id, returning it if it had not been delivered yet.  For a saga
+    /// timeout, this is called when the awaited event arrives in time.
+    pub fn cancel(&mut self, id: &str) -> Option<ScheduledMessage<M>> {
+        self.store.remove(&id.to_string())
+    }
+
+    /// Hands every message that has come due to the handler in due order, and returns the handler's results in the same
+    /// order.  Each message is removed from the store before it's handled, so a message is delivered at most once.
+    pub fn deliver_due<H: Handles<M>>(&mut self, handler: &mut H) -> Vec<H::Result> {
+        let now = self.clock.now();
+
+        self.store.due(now)
+            .into_iter()
+            .filter_map(|scheduled| self.store.remove(&scheduled.id))
+            .map(|scheduled| handler.handle(scheduled.message))
+            .collect()
+    }
+}
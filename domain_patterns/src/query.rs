@@ -1,3 +1,13 @@
+use crate::command::Handles;
+use crate::event::{DomainEvent, EventVariant};
+use crate::time::{Clock, Timestamp};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
+
 /// Query is a simple marker trait that should be placed on query types which we plan to handle with
 /// a QueryHandler (a struct that implements HandlesQuery)
 pub trait Query {}
@@ -5,8 +15,216 @@ pub trait Query {}
 /// HandlesQuery is a trait that you apply to a struct, which knows how to handle a query.  A query
 /// is a struct or enum that implements the Query trait, and is a parameter object that we can use
 /// to construct a custom query.
+///
+/// Query handlers that return many results should page them, and return a `Page` from the collections module so
+/// callers get the total count and whether there are more results in a consistent shape.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::collections::Page;
+/// use domain_patterns::query::{Query, HandlesQuery};
+///
+/// struct ListNames {
+///     page_num: usize,
+///     page_size: usize,
+/// }
+///
+/// impl Query for ListNames {}
+///
+/// struct NamesHandler {
+///     names: Vec<String>,
+/// }
+///
+/// impl HandlesQuery<ListNames> for NamesHandler {
+///     type Result = Result<Page<String>, String>;
+///
+///     fn handle(&mut self, query: ListNames) -> Self::Result {
+///         Ok(Page::from_slice(&self.names, query.page_num, query.page_size))
+///     }
+/// }
+///
+/// let mut handler = NamesHandler { names: vec!["alice".to_string(), "bob".to_string()] };
+/// let page = handler.handle(ListNames { page_num: 1, page_size: 1 }).unwrap();
+/// assert_eq!(page.items, vec!["alice".to_string()]);
+/// assert!(page.has_more);
+/// ```
 pub trait HandlesQuery<T: Query> {
     type Result;
 
     fn handle(&mut self, query: T) -> Self::Result;
 }
+
+/// CacheableQuery should be implemented by queries whose results may be cached by a `QueryDispatcher`.
+pub trait CacheableQuery: Query {
+    /// cache_key should uniquely identify the query's parameters.  Two queries of the same type with the same
+    /// cache key are expected to return the same result.
+    fn cache_key(&self) -> String;
+
+    /// aggregate_ids returns the ids of the aggregates the query result was built from.  When an invalidating event
+    /// is published for any of these aggregates, the cached result is dropped.  A query that returns an empty list
+    /// is only ever evicted by its time to live.
+    fn aggregate_ids(&self) -> Vec<String>;
+}
+
+// A single cached query result.
+struct CacheEntry {
+    value: Box<dyn Any>,
+    aggregate_ids: Vec<String>,
+    expires: Timestamp,
+}
+
+// A predicate that decides whether an event should invalidate cached results.
+type Invalidator<E> = Box<dyn Fn(&E) -> bool>;
+
+// The cache is shared between the dispatcher and any `CacheInvalidator` subscribed to an event bus.
+struct QueryCache<E> {
+    entries: HashMap<(TypeId, String), CacheEntry>,
+    invalidators: Vec<Invalidator<E>>,
+}
+
+impl<E: DomainEvent> QueryCache<E> {
+    fn invalidate(&mut self, event: &E) -> usize {
+        if !self.invalidators.iter().any(|invalidates| invalidates(event)) {
+            return 0;
+        }
+
+        let aggregate_id = event.aggregate_id();
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| !entry.aggregate_ids.contains(&aggregate_id));
+
+        before - self.entries.len()
+    }
+}
+
+/// QueryDispatcher wraps a query handler and caches the results of `CacheableQuery` queries by their cache key, for
+/// a fixed time to live.  Only successful results are cached.
+///
+/// Cached results can also be invalidated by domain events.  Choose which event variants should invalidate results
+/// with `invalidate_on`, and subscribe the dispatcher's `invalidator()` to the `EventBus`.  Whenever one of those
+/// events is published, every cached result whose query depends on the event's aggregate id is dropped.
+///
+/// Time is read from a `Clock`, so expiry can be tested with a `FixedClock`.
+pub struct QueryDispatcher<H, E, C> {
+    handler: H,
+    clock: C,
+    ttl: Duration,
+    cache: Rc<RefCell<QueryCache<E>>>,
+}
+
+impl<H, E, C> QueryDispatcher<H, E, C>
+    where E: DomainEvent + 'static,
+          C: Clock,
+{
+    /// Creates a dispatcher that caches results for the `ttl` duration.
+    pub fn new(handler: H, clock: C, ttl: Duration) -> QueryDispatcher<H, E, C> {
+        QueryDispatcher {
+            handler,
+            clock,
+            ttl,
+            cache: Rc::new(RefCell::new(QueryCache {
+                entries: HashMap::new(),
+                invalidators: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns a reference to the wrapped query handler.
+    pub fn inner(&self) -> &H {
+        &self.handler
+    }
+
+    /// Configures the event variant `V` to invalidate cached results for the event's aggregate id.
+    pub fn invalidate_on<V: EventVariant<E>>(&mut self) {
+        self.cache.borrow_mut().invalidators.push(Box::new(|event: &E| V::from_event(event).is_some()));
+    }
+
+    /// Returns a handler that invalidates this dispatcher's cache, meant to be subscribed to an `EventBus`.  The error
+    /// type `Err` should match the bus' error type.  Invalidation never fails.
+    pub fn invalidator<Err>(&self) -> CacheInvalidator<E, Err> {
+        CacheInvalidator {
+            cache: self.cache.clone(),
+            error: PhantomData,
+        }
+    }
+
+    /// Invalidates cached results for the event's aggregate id, if the event is one of the configured invalidating
+    /// events.  Returns the number of cached results dropped.
+    pub fn invalidate(&mut self, event: &E) -> usize {
+        self.cache.borrow_mut().invalidate(event)
+    }
+
+    /// Drops every cached result.
+    pub fn clear(&mut self) {
+        self.cache.borrow_mut().entries.clear();
+    }
+
+    /// Returns the number of results currently cached, including any that expired but were not looked up since.
+    pub fn num_cached(&self) -> usize {
+        self.cache.borrow().entries.len()
+    }
+
+    /// Returns a cached result for the query if there is one that has not expired, and otherwise hands the query to
+    /// the wrapped handler and caches the result if it was successful.
+    pub fn dispatch<Q, T, Err>(&mut self, query: Q) -> Result<T, Err>
+        where Q: CacheableQuery + 'static,
+              H: HandlesQuery<Q, Result = Result<T, Err>>,
+              T: Clone + 'static,
+    {
+        let key = (TypeId::of::<Q>(), query.cache_key());
+        let now = self.clock.now();
+
+        {
+            let mut cache = self.cache.borrow_mut();
+            let cached = cache.entries.get(&key)
+                .filter(|entry| entry.expires > now)
+                .and_then(|entry| entry.value.downcast_ref::<T>())
+                .cloned();
+
+            match cached {
+                Some(result) => return Ok(result),
+                None => { cache.entries.remove(&key); },
+            }
+        }
+
+        let aggregate_ids = query.aggregate_ids();
+        let result = self.handler.handle(query)?;
+
+        self.cache.borrow_mut().entries.insert(key, CacheEntry {
+            value: Box::new(result.clone()),
+            aggregate_ids,
+            expires: now.saturating_add(self.ttl),
+        });
+
+        Ok(result)
+    }
+}
+
+impl<Q, H, E, C, T, Err> HandlesQuery<Q> for QueryDispatcher<H, E, C>
+    where Q: CacheableQuery + 'static,
+          H: HandlesQuery<Q, Result = Result<T, Err>>,
+          E: DomainEvent + 'static,
+          C: Clock,
+          T: Clone + 'static,
+{
+    type Result = Result<T, Err>;
+
+    fn handle(&mut self, query: Q) -> Self::Result {
+        self.dispatch(query)
+    }
+}
+
+/// CacheInvalidator is an event handler that invalidates the cache of the `QueryDispatcher` it was created from.
+/// Subscribe it to an `EventBus` to keep cached query results in step with published events.
+pub struct CacheInvalidator<E, Err> {
+    cache: Rc<RefCell<QueryCache<E>>>,
+    error: PhantomData<fn() -> Err>,
+}
+
+impl<E: DomainEvent, Err> Handles<E> for CacheInvalidator<E, Err> {
+    type Result = Result<(), Err>;
+
+    fn handle(&mut self, msg: E) -> Self::Result {
+        self.cache.borrow_mut().invalidate(&msg);
+        Ok(())
+    }
+}
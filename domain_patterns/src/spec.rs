@@ -0,0 +1,398 @@
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+/// FieldValue is a backend neutral representation of a single field of a read model, used as the operand of
+/// filters in a `QuerySpec`.  It maps directly onto SQLite's storage classes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl FieldValue {
+    // Orders values the way SQLite does: nulls first, then numbers, then text.
+    fn compare(&self, other: &FieldValue) -> Ordering {
+        if let (Some(a), Some(b)) = (self.as_number(), other.as_number()) {
+            return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        }
+
+        match (self, other) {
+            (FieldValue::Text(a), FieldValue::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            FieldValue::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            FieldValue::Int(i) => Some(*i as f64),
+            FieldValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            FieldValue::Null => 0,
+            FieldValue::Bool(_) | FieldValue::Int(_) | FieldValue::Float(_) => 1,
+            FieldValue::Text(_) => 2,
+        }
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> FieldValue {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<i32> for FieldValue {
+    fn from(value: i32) -> FieldValue {
+        FieldValue::Int(i64::from(value))
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> FieldValue {
+        FieldValue::Int(value)
+    }
+}
+
+impl From<u32> for FieldValue {
+    fn from(value: u32) -> FieldValue {
+        FieldValue::Int(i64::from(value))
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> FieldValue {
+        FieldValue::Float(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> FieldValue {
+        FieldValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> FieldValue {
+        FieldValue::Text(value)
+    }
+}
+
+impl<V: Into<FieldValue>> From<Option<V>> for FieldValue {
+    fn from(value: Option<V>) -> FieldValue {
+        match value {
+            Some(v) => v.into(),
+            None => FieldValue::Null,
+        }
+    }
+}
+
+/// Filter is a single condition on a field of a read model.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// The field is equal to the value.  Comparing with `FieldValue::Null` checks that the field is null.
+    Eq(String, FieldValue),
+    /// The field falls within the bounds.
+    Range(String, Bound<FieldValue>, Bound<FieldValue>),
+    /// The field is equal to one of the values.
+    In(String, Vec<FieldValue>),
+    /// The field is text that starts with the prefix.  The comparison is case sensitive.
+    Prefix(String, String),
+}
+
+impl Filter {
+    fn matches<T: Fields>(&self, item: &T) -> bool {
+        let value = |field: &str| item.field(field).unwrap_or(FieldValue::Null);
+
+        match self {
+            Filter::Eq(field, expected) => value(field).compare(expected) == Ordering::Equal,
+            Filter::Range(field, min, max) => {
+                let actual = value(field);
+                if actual == FieldValue::Null {
+                    return false;
+                }
+
+                let above_min = match min {
+                    Bound::Included(min) => actual.compare(min) != Ordering::Less,
+                    Bound::Excluded(min) => actual.compare(min) == Ordering::Greater,
+                    Bound::Unbounded => true,
+                };
+                let below_max = match max {
+                    Bound::Included(max) => actual.compare(max) != Ordering::Greater,
+                    Bound::Excluded(max) => actual.compare(max) == Ordering::Less,
+                    Bound::Unbounded => true,
+                };
+
+                above_min && below_max
+            },
+            Filter::In(field, values) => {
+                let actual = value(field);
+                actual != FieldValue::Null && values.iter().any(|v| actual.compare(v) == Ordering::Equal)
+            },
+            Filter::Prefix(field, prefix) => match value(field) {
+                FieldValue::Text(text) => text.starts_with(prefix.as_str()),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// SortDirection is the direction a `SortKey` orders results in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// SortKey orders results by a single field.  Null values sort first when ascending.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+/// Fields is implemented by read models that can be evaluated against a `QuerySpec` in memory.  It exposes the
+/// fields that queries may filter and sort on by name.
+pub trait Fields {
+    /// field returns the value of the named field, or [`None`] if the read model has no such field.  Missing
+    /// fields are treated as null.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn field(&self, name: &str) -> Option<FieldValue>;
+}
+
+/// QuerySpec is a backend neutral description of a query over read models.  It holds field filters, which must all
+/// match, sort keys which are applied in order, and an optional limit on the number of results.
+///
+/// Read repositories take a `QuerySpec` in `ReadRepository::query`.  In memory stores can evaluate it against any
+/// read model that implements `Fields` with `apply`, and SQL backed stores can translate it with `to_sql`.
+///
+/// # Example
+/// ```rust
+/// use domain_patterns::spec::{QuerySpec, Fields, FieldValue};
+///
+/// struct UserDto {
+///     name: String,
+///     age: i64,
+/// }
+///
+/// impl Fields for UserDto {
+///     fn field(&self, name: &str) -> Option<FieldValue> {
+///         match name {
+///             "name" => Some(self.name.clone().into()),
+///             "age" => Some(self.age.into()),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let users = vec![
+///     UserDto { name: "alice".to_string(), age: 31 },
+///     UserDto { name: "bob".to_string(), age: 17 },
+///     UserDto { name: "carol".to_string(), age: 45 },
+/// ];
+///
+/// let spec = QuerySpec::new()
+///     .range("age", 18..)
+///     .sort_desc("age")
+///     .limit(1);
+///
+/// let results = spec.apply(users);
+/// assert_eq!(results.len(), 1);
+/// assert_eq!(results[0].name, "carol");
+///
+/// let sql = spec.to_sql("users");
+/// assert_eq!(sql.sql, r#"SELECT * FROM "users" WHERE "age" >= ? ORDER BY "age" DESC LIMIT 1"#);
+/// assert_eq!(sql.params, vec![FieldValue::Int(18)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QuerySpec {
+    pub filters: Vec<Filter>,
+    pub sort: Vec<SortKey>,
+    pub limit: Option<usize>,
+}
+
+impl QuerySpec {
+    pub fn new() -> QuerySpec {
+        QuerySpec::default()
+    }
+
+    /// Only matches read models whose field is equal to the value.
+    pub fn eq<V: Into<FieldValue>>(mut self, field: &str, value: V) -> QuerySpec {
+        self.filters.push(Filter::Eq(field.to_string(), value.into()));
+        self
+    }
+
+    /// Only matches read models whose field falls within the range, for example `18..65` or `..=100`.
+    pub fn range<V, R>(mut self, field: &str, range: R) -> QuerySpec
+        where V: Into<FieldValue> + Clone,
+              R: RangeBounds<V>,
+    {
+        let bound = |b: Bound<&V>| match b {
+            Bound::Included(v) => Bound::Included(v.clone().into()),
+            Bound::Excluded(v) => Bound::Excluded(v.clone().into()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        self.filters.push(Filter::Range(field.to_string(), bound(range.start_bound()), bound(range.end_bound())));
+        self
+    }
+
+    /// Only matches read models whose field is equal to one of the values.  An empty list matches nothing.
+    pub fn is_in<V, I>(mut self, field: &str, values: I) -> QuerySpec
+        where V: Into<FieldValue>,
+              I: IntoIterator<Item = V>,
+    {
+        self.filters.push(Filter::In(field.to_string(), values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Only matches read models whose field is text starting with the prefix, matching case exactly.
+    pub fn prefix(mut self, field: &str, prefix: &str) -> QuerySpec {
+        self.filters.push(Filter::Prefix(field.to_string(), prefix.to_string()));
+        self
+    }
+
+    /// Sorts results by the field in ascending order, after any sort keys added before it.
+    pub fn sort_asc(mut self, field: &str) -> QuerySpec {
+        self.sort.push(SortKey { field: field.to_string(), direction: SortDirection::Ascending });
+        self
+    }
+
+    /// Sorts results by the field in descending order, after any sort keys added before it.
+    pub fn sort_desc(mut self, field: &str) -> QuerySpec {
+        self.sort.push(SortKey { field: field.to_string(), direction: SortDirection::Descending });
+        self
+    }
+
+    /// Returns at most `limit` results.
+    pub fn limit(mut self, limit: usize) -> QuerySpec {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns `true` if the read model matches every filter.
+    pub fn matches<T: Fields>(&self, item: &T) -> bool {
+        self.filters.iter().all(|filter| filter.matches(item))
+    }
+
+    /// Evaluates the query in memory, filtering, sorting and limiting the supplied read models.  Read models that
+    /// are equal on every sort key keep their original order.
+    pub fn apply<T: Fields>(&self, items: Vec<T>) -> Vec<T> {
+        let mut results: Vec<T> = items.into_iter().filter(|item| self.matches(item)).collect();
+
+        if !self.sort.is_empty() {
+            results.sort_by(|a, b| {
+                self.sort.iter()
+                    .map(|key| {
+                        let a = a.field(&key.field).unwrap_or(FieldValue::Null);
+                        let b = b.field(&key.field).unwrap_or(FieldValue::Null);
+                        match key.direction {
+                            SortDirection::Ascending => a.compare(&b),
+                            SortDirection::Descending => b.compare(&a),
+                        }
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+
+        results
+    }
+
+    /// Translates the query into a SQLite `SELECT` statement over `table`.  Values are never inlined into the
+    /// statement, they're returned as positional parameters in the order their `?` placeholders appear, ready to
+    /// be bound by the database driver.
+    pub fn to_sql(&self, table: &str) -> SqlQuery {
+        let mut params = Vec::new();
+        let mut conditions = Vec::new();
+
+        for filter in &self.filters {
+            match filter {
+                Filter::Eq(field, FieldValue::Null) => conditions.push(format!("{} IS NULL", quote(field))),
+                Filter::Eq(field, value) => {
+                    conditions.push(format!("{} = ?", quote(field)));
+                    params.push(value.clone());
+                },
+                Filter::Range(field, min, max) => {
+                    let mut bound = |b: &Bound<FieldValue>, inclusive: &str, exclusive: &str| match b {
+                        Bound::Included(v) => {
+                            conditions.push(format!("{} {} ?", quote(field), inclusive));
+                            params.push(v.clone());
+                        },
+                        Bound::Excluded(v) => {
+                            conditions.push(format!("{} {} ?", quote(field), exclusive));
+                            params.push(v.clone());
+                        },
+                        Bound::Unbounded => (),
+                    };
+                    bound(min, ">=", ">");
+                    bound(max, "<=", "<");
+                    if let (Bound::Unbounded, Bound::Unbounded) = (min, max) {
+                        conditions.push(format!("{} IS NOT NULL", quote(field)));
+                    }
+                },
+                Filter::In(_, values) if values.is_empty() => conditions.push("0 = 1".to_string()),
+                Filter::In(field, values) => {
+                    let placeholders = vec!["?"; values.len()].join(", ");
+                    conditions.push(format!("{} IN ({})", quote(field), placeholders));
+                    params.extend(values.iter().cloned());
+                },
+                // LIKE ignores ASCII case in SQLite, so the prefix is compared directly to keep it case sensitive,
+                // as it is in `apply`.  substr turns numbers into text, so other column types are ruled out first.
+                Filter::Prefix(field, prefix) => {
+                    conditions.push(format!("typeof({0}) = 'text' AND substr({0}, 1, length(?)) = ?", quote(field)));
+                    params.push(FieldValue::Text(prefix.clone()));
+                    params.push(FieldValue::Text(prefix.clone()));
+                },
+            }
+        }
+
+        let mut sql = format!("SELECT * FROM {}", quote(table));
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        if !self.sort.is_empty() {
+            let keys: Vec<String> = self.sort.iter()
+                .map(|key| match key.direction {
+                    SortDirection::Ascending => format!("{} ASC", quote(&key.field)),
+                    SortDirection::Descending => format!("{} DESC", quote(&key.field)),
+                })
+                .collect();
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&keys.join(", "));
+        }
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        SqlQuery { sql, params }
+    }
+}
+
+/// SqlQuery is a `QuerySpec` translated into a SQL statement, along with the parameters to bind to its `?`
+/// placeholders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlQuery {
+    pub sql: String,
+    pub params: Vec<FieldValue>,
+}
+
+// Quotes an identifier so field and table names can't be used to inject SQL.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -0,0 +1,150 @@
+use crate::models::{Id, ValueObject};
+use crate::time::Timestamp;
+use proptest::arbitrary::{any, Arbitrary};
+use proptest::strategy::{BoxedStrategy, Strategy, Union};
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+/// proptest is re-exported so the code generated by `#[value_object(arbitrary)]` and `#[event(arbitrary)]` doesn't
+/// need your crate to depend on it directly.
+pub use proptest;
+
+/// The latest timestamp generated by `timestamps()`, the last millisecond of the year 9999.
+const MAX_MILLIS: i64 = 253_402_300_799_999;
+
+/// ValueStrategy is implemented for value objects by `#[value_object(arbitrary)]`.  Valid value objects are
+/// generated with proptest's `any::<V>()`, as the value object implements `Arbitrary`, and `invalid()` generates
+/// inputs that fail validation, for negative tests.
+///
+/// # Example
+/// ```rust
+/// #[macro_use]
+/// extern crate domain_derive;
+///
+/// use domain_patterns::models::ValueObject;
+/// use domain_patterns::strategy::ValueStrategy;
+/// use domain_patterns::strategy::proptest::prelude::*;
+/// use domain_patterns::strategy::proptest::test_runner::TestRunner;
+/// use std::convert::TryFrom;
+///
+/// #[derive(ValueSetup, Debug)]
+/// #[value_object(min_len = 3, max_len = 20, arbitrary)]
+/// pub struct Username(String);
+///
+/// fn main() {
+///     let mut runner = TestRunner::default();
+///
+///     runner.run(&any::<Username>(), |username| {
+///         prop_assert!(Username::validate(&username.value()).is_ok());
+///         Ok(())
+///     }).unwrap();
+///
+///     runner.run(&Username::invalid(), |input| {
+///         prop_assert!(Username::try_from(input).is_err());
+///         Ok(())
+///     }).unwrap();
+/// }
+/// ```
+pub trait ValueStrategy<T>: ValueObject<T> + Arbitrary {
+    /// Returns a strategy for inputs that fail `validate`, so `try_from` returns an error for every input.
+    fn invalid() -> BoxedStrategy<T>;
+}
+
+/// valid turns a strategy for inputs into a strategy for value objects, by passing every input through `try_from`
+/// and rejecting the inputs that fail validation.  The inputs should mostly be valid, or proptest gives up.
+pub fn valid<V, T, S>(inputs: S) -> BoxedStrategy<V>
+    where V: TryFrom<T> + Debug + 'static,
+          S: Strategy<Value = T> + 'static,
+{
+    inputs.prop_filter_map("input failed validation", |input| V::try_from(input).ok()).boxed()
+}
+
+/// invalid keeps the inputs of a strategy that fail the value object's `validate`.  The inputs should mostly be
+/// invalid, or proptest gives up.
+pub fn invalid<V, T, S>(inputs: S) -> BoxedStrategy<T>
+    where V: ValueObject<T>,
+          T: Debug + 'static,
+          S: Strategy<Value = T> + 'static,
+{
+    inputs.prop_filter("input passed validation", |input| V::validate(input).is_err()).boxed()
+}
+
+/// matching returns a strategy for strings that match the regular expression.  A leading `^` and a trailing `$` are
+/// dropped, as the generated strings match from start to end anyway.
+///
+/// # Panics
+///
+/// Panics if the regular expression can't be used for generation, such as when it uses word boundaries.
+pub fn matching(regex: &str) -> BoxedStrategy<String> {
+    let unanchored = regex.strip_prefix('^').unwrap_or(regex);
+    let unanchored = match unanchored.strip_suffix('$') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => unanchored,
+    };
+
+    proptest::string::string_regex(unanchored)
+        .unwrap_or_else(|e| panic!("can't generate strings matching {}: {}", regex, e))
+        .boxed()
+}
+
+/// lengths returns a strategy for alphanumeric strings with a length between `min` and `max`, inclusive.  Without a
+/// `max`, strings are at most 32 characters longer than `min`.
+pub fn lengths(min: Option<usize>, max: Option<usize>) -> BoxedStrategy<String> {
+    let min = min.unwrap_or(0);
+    let max = max.unwrap_or(min + 32);
+    matching(&format!("[a-zA-Z0-9]{{{},{}}}", min, max))
+}
+
+/// wrong_lengths returns a strategy for alphanumeric strings that are shorter than `min` or longer than `max`.
+///
+/// # Panics
+///
+/// Panics if every length is valid, as there's no `min` above 0 and no `max`.
+pub fn wrong_lengths(min: Option<usize>, max: Option<usize>) -> BoxedStrategy<String> {
+    let mut strategies = vec![];
+    if let Some(min) = min.filter(|min| *min > 0) {
+        strategies.push(matching(&format!("[a-zA-Z0-9]{{0,{}}}", min - 1)));
+    }
+    if let Some(max) = max {
+        strategies.push(matching(&format!("[a-zA-Z0-9]{{{},{}}}", max + 1, max + 32)));
+    }
+
+    assert!(!strategies.is_empty(), "every length is valid without a min above 0 or a max");
+    Union::new(strategies).boxed()
+}
+
+/// ids returns a strategy for random version 4 UUIDs, formatted as lowercase hyphenated strings, which parse into
+/// `Uuid`, `Id<T>` or `String` ids.
+pub fn ids() -> BoxedStrategy<String> {
+    any::<u128>()
+        .prop_map(|n| {
+            let n = (n & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+            let hex = format!("{:032x}", n);
+            format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+        })
+        .boxed()
+}
+
+/// timestamps returns a strategy for timestamps between the unix epoch and the end of the year 9999, which can be
+/// converted into any of the `occurred` types the `DomainEvent` derive accepts.
+pub fn timestamps() -> BoxedStrategy<Timestamp> {
+    (0..=MAX_MILLIS).prop_map(Timestamp::from_millis).boxed()
+}
+
+impl Arbitrary for Timestamp {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Timestamp>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        timestamps()
+    }
+}
+
+impl<T: 'static> Arbitrary for Id<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Id<T>>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        valid(ids())
+    }
+}
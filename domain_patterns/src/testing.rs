@@ -0,0 +1,487 @@
+use crate::collections::{AppendError, EventRepository, ReadRepository, Repository, VersionConflictError};
+use crate::command::{AggregateCommand, Handles};
+use crate::event::DomainEvent;
+use crate::models::{AggregateRoot, Applier, Decider};
+use crate::spec::QuerySpec;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Write};
+use std::rc::Rc;
+
+/// given starts a test scenario for a `Decider`, whose state is built by evolving the initial state with the
+/// supplied events.  Follow it with `when` to decide a command.
+///
+/// # Example
+/// ```rust
+/// # use domain_patterns::command::Command;
+/// # use domain_patterns::event::DomainEvent;
+/// # use domain_patterns::message::Message;
+/// # use domain_patterns::time::Timestamp;
+/// use domain_patterns::models::Decider;
+/// use domain_patterns::testing::given;
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Deposited { amount: u64, version: u64 }
+/// # impl Message for Deposited {}
+/// # impl DomainEvent for Deposited {
+/// #     fn occurred(&self) -> Timestamp { 0.into() }
+/// #     fn id(&self) -> String { self.version.to_string() }
+/// #     fn aggregate_id(&self) -> String { "account".to_string() }
+/// #     fn version(&self) -> u64 { self.version }
+/// # }
+///
+/// struct Deposit { amount: u64 }
+/// # impl Message for Deposit {}
+/// # impl Command for Deposit {}
+///
+/// struct Account { balance: u64, version: u64 }
+///
+/// impl Decider for Account {
+///     type Command = Deposit;
+///     type Events = Deposited;
+///     type Error = String;
+///
+///     fn initial() -> Self {
+///         Account { balance: 0, version: 0 }
+///     }
+///
+///     fn decide(&self, command: Deposit) -> Result<Vec<Deposited>, String> {
+///         if self.balance + command.amount > 100 {
+///             return Err("balance limit exceeded".to_string());
+///         }
+///         Ok(vec![Deposited { amount: command.amount, version: self.version + 1 }])
+///     }
+///
+///     fn evolve(self, event: &Deposited) -> Self {
+///         Account { balance: self.balance + event.amount, version: event.version }
+///     }
+/// }
+///
+/// given::<Account>(vec![Deposited { amount: 60, version: 1 }])
+///     .when(Deposit { amount: 30 })
+///     .then_expect(vec![Deposited { amount: 30, version: 2 }]);
+///
+/// given::<Account>(vec![Deposited { amount: 60, version: 1 }])
+///     .when(Deposit { amount: 50 })
+///     .then_error(|e| e == "balance limit exceeded");
+/// ```
+pub fn given<D: Decider>(events: Vec<D::Events>) -> Given<D> {
+    let state = events.iter().fold(D::initial(), |state, event| state.evolve(event));
+
+    Given {
+        state,
+    }
+}
+
+/// given_aggregate starts a test scenario for an `Applier` aggregate, by applying the supplied events to the
+/// aggregate.  Follow it with `when` to run a method of the aggregate.
+///
+/// # Panics
+///
+/// Panics if any of the events can't be applied, since the scenario can't be set up.
+pub fn given_aggregate<A>(mut aggregate: A, events: Vec<A::Events>) -> GivenAggregate<A>
+    where A: Applier,
+          A::EventError: Debug,
+{
+    for (i, event) in events.into_iter().enumerate() {
+        if let Err(e) = aggregate.apply(event) {
+            panic!("given event [{}] could not be applied: {:?}", i, e);
+        }
+    }
+
+    GivenAggregate {
+        aggregate,
+    }
+}
+
+/// given_events starts a test scenario for a command handler that stores events in an `EventRepository`, such as
+/// a `DeciderHandler`.  The given events are inserted into `repo`, which is then shared as an `Rc<RefCell<R>>`.
+/// Follow it with `handled_by` to build the handler around the repository, and `when` to handle a command.
+///
+/// # Example
+/// ```rust,ignore
+/// given_events(InMemoryEventRepository::new(), vec![account_opened])
+///     .handled_by(DeciderHandler::<Account, _>::new)
+///     .when(Deposit { account_id, amount: 30 })
+///     .then_expect(vec![deposited]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the repository refuses any of the events, since the scenario can't be set up.
+pub fn given_events<R>(mut repo: R, events: Vec<R::Events>) -> GivenEvents<R>
+    where R: EventRepository,
+          R::Events: Debug,
+{
+    for (i, event) in events.iter().enumerate() {
+        if repo.insert(event).is_none() {
+            panic!("given event [{}] could not be inserted, an event with the same id is already stored: {:?}", i, event);
+        }
+    }
+
+    GivenEvents {
+        repo: Rc::new(RefCell::new(repo)),
+    }
+}
+
+/// Given holds the state of a `Decider` built from the given events.
+pub struct Given<D> {
+    state: D,
+}
+
+impl<D: Decider> Given<D> {
+    /// Returns the state built from the given events.
+    pub fn state(&self) -> &D {
+        &self.state
+    }
+
+    /// Decides the command against the state built from the given events.
+    pub fn when(self, command: D::Command) -> Then<D::Events, D::Error> {
+        Then {
+            result: self.state.decide(command),
+        }
+    }
+}
+
+/// GivenAggregate holds an `Applier` aggregate with the given events applied.
+pub struct GivenAggregate<A> {
+    aggregate: A,
+}
+
+impl<A: Applier> GivenAggregate<A> {
+    /// Returns the aggregate with the given events applied.
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+
+    /// Runs the supplied function, which should call a method of the aggregate that returns the events it
+    /// raised, or an error.
+    pub fn when<F>(mut self, f: F) -> Then<A::Events, A::Error>
+        where F: FnOnce(&mut A) -> Result<Vec<A::Events>, A::Error>,
+    {
+        Then {
+            result: f(&mut self.aggregate),
+        }
+    }
+}
+
+/// GivenEvents holds an event repository with the given events stored.
+pub struct GivenEvents<R> {
+    repo: Rc<RefCell<R>>,
+}
+
+impl<R: EventRepository> GivenEvents<R> {
+    /// Returns the repository with the given events stored.
+    pub fn repo(&self) -> &Rc<RefCell<R>> {
+        &self.repo
+    }
+
+    /// Builds the command handler under test, passing it a handle to the repository.
+    pub fn handled_by<H, F>(self, make_handler: F) -> GivenHandler<R, H>
+        where F: FnOnce(Rc<RefCell<R>>) -> H,
+    {
+        GivenHandler {
+            handler: make_handler(self.repo.clone()),
+            repo: self.repo,
+        }
+    }
+}
+
+/// GivenHandler holds a command handler along with the repository it stores events in.
+pub struct GivenHandler<R, H> {
+    repo: Rc<RefCell<R>>,
+    handler: H,
+}
+
+impl<R: EventRepository, H> GivenHandler<R, H> {
+    /// Handles the command.  If the handler succeeds, the outcome is the events appended to the repository for
+    /// the command's aggregate while the command was handled, rather than whatever the handler returned.
+    pub fn when<C, T, Err>(mut self, command: C) -> Then<R::Events, Err>
+        where C: AggregateCommand,
+              H: Handles<C, Result = Result<T, Err>>,
+    {
+        let aggregate_id = command.aggregate_id();
+        let version = self.repo.current_version(&aggregate_id);
+        let result = self.handler.handle(command)
+            .map(|_| self.repo.events_since_version(&aggregate_id, version).unwrap_or_default());
+
+        Then {
+            result,
+        }
+    }
+}
+
+/// Then holds the outcome of a scenario, and asserts on it.
+pub struct Then<E, Err> {
+    result: Result<Vec<E>, Err>,
+}
+
+impl<E: Debug, Err: Debug> Then<E, Err> {
+    /// Asserts that the scenario succeeded and raised exactly the expected events, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scenario failed, or if the events differ.  The message lists every event that differs by
+    /// index, along with any missing or unexpected events.
+    pub fn then_expect(self, expected: Vec<E>)
+        where E: PartialEq,
+    {
+        let actual = match self.result {
+            Ok(events) => events,
+            Err(e) => panic!("expected events, but the scenario failed with: {:?}", e),
+        };
+
+        if let Some(diff) = diff_events(&expected, &actual) {
+            panic!("the raised events did not match the expected events:\n{}", diff);
+        }
+    }
+
+    /// Asserts that the scenario failed with an error for which the predicate returns true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scenario succeeded, listing the events it raised, or if the predicate rejects the error.
+    pub fn then_error<P>(self, predicate: P)
+        where P: FnOnce(&Err) -> bool,
+    {
+        match self.result {
+            Ok(events) => panic!("expected an error, but the scenario raised events: {:?}", events),
+            Err(e) => assert!(predicate(&e), "the scenario failed with an unexpected error: {:?}", e),
+        }
+    }
+
+    /// Returns the outcome of the scenario, for assertions the other methods don't cover.
+    pub fn into_result(self) -> Result<Vec<E>, Err> {
+        self.result
+    }
+}
+
+/// repository_conformance checks that a `Repository` implementation follows the documented contract of each of
+/// its methods.  `repo` should be empty, and `new_entity` should return an entity with a new, unique id every time
+/// it's called.
+///
+/// The suite inserts, updates, pages through and removes entities, so point it at a test database.
+///
+/// # Panics
+///
+/// Panics with a message naming the method and the rule it broke if the repository doesn't follow the contract,
+/// or if the repository returns an error.
+///
+/// # Example
+/// ```rust,ignore
+/// #[test]
+/// fn test_user_repository_conformance() {
+///     repository_conformance(PostgresUserRepository::new(test_pool()), || User::new(Uuid::new_v4()));
+/// }
+/// ```
+pub fn repository_conformance<T, R, F>(mut repo: R, mut new_entity: F)
+    where T: AggregateRoot,
+          R: Repository<T>,
+          F: FnMut() -> T,
+{
+    let entities: Vec<T> = (0..3).map(|_| new_entity()).collect();
+    let keys: Vec<String> = entities.iter().map(|e| e.id()).collect();
+    assert_eq!(keys.iter().collect::<BTreeSet<_>>().len(), keys.len(), "new_entity must return entities with unique ids");
+
+    let (entity, key) = (&entities[0], &keys[0]);
+    assert!(ok(repo.get(key), "get").is_none(), "get returned an entity for a key that was never inserted");
+    assert!(!ok(repo.contains_key(key), "contains_key"), "contains_key returned true for a key that was never inserted");
+    assert_eq!(ok(repo.update(entity), "update"), None, "update must return None for an entity that isn't stored");
+    assert!(!ok(repo.contains_key(key), "contains_key"), "update must not insert an entity that isn't stored");
+    assert_eq!(ok(repo.remove(key), "remove"), None, "remove must return None for a key that isn't stored");
+
+    for (e, k) in entities.iter().zip(&keys) {
+        assert_eq!(ok(repo.insert(e), "insert"), Some(k.clone()), "insert must return the key of a new entity");
+    }
+    assert_eq!(ok(repo.insert(entity), "insert"), None, "insert must return None when the key is already stored");
+
+    assert!(ok(repo.get(key), "get").as_ref() == Some(entity), "get must return the entity stored at the key");
+    assert!(ok(repo.contains_key(key), "contains_key"), "contains_key returned false for a stored key");
+    assert_eq!(ok(repo.update(entity), "update"), Some(key.clone()), "update must return the key of a stored entity");
+
+    let mut paged = Vec::new();
+    for page_num in 1..=2 {
+        let page = ok(repo.get_paged(page_num, 2), "get_paged");
+        assert!(page.len() <= 2, "get_paged returned {} items for a page size of 2", page.len());
+        if let Some(total) = page.total {
+            assert_eq!(total, keys.len(), "get_paged must set the total to the number of stored entities");
+        }
+        paged.extend(page.items.iter().map(|e| e.id()));
+    }
+    assert_same_keys(paged, &keys, "get_paged");
+    let past_the_end = ok(repo.get_paged(3, 2), "get_paged");
+    assert!(past_the_end.is_empty(), "get_paged must return an empty page past the last page");
+    assert!(!past_the_end.has_more, "get_paged must not report more items past the last page");
+
+    assert_eq!(ok(repo.remove(key), "remove"), Some(key.clone()), "remove must return the key of a stored entity");
+    assert!(ok(repo.get(key), "get").is_none(), "get returned an entity after it was removed");
+    assert!(!ok(repo.contains_key(key), "contains_key"), "contains_key returned true after the entity was removed");
+    assert_eq!(ok(repo.remove(key), "remove"), None, "remove must return None once the entity has been removed");
+}
+
+/// read_repository_conformance checks that a `ReadRepository` implementation follows the documented contract of
+/// each of its methods.  Since read repositories can't be written to, `repo` should already hold exactly the read
+/// models stored at `keys`, and `key_of` should return the key a read model is stored at.
+///
+/// # Panics
+///
+/// Panics with a message naming the method and the rule it broke if the repository doesn't follow the contract,
+/// or if the repository returns an error.
+pub fn read_repository_conformance<T, R, K>(mut repo: R, keys: &[String], key_of: K)
+    where R: ReadRepository<T>,
+          K: Fn(&T) -> String,
+{
+    for key in keys {
+        let item = ok(repo.get(key), "get");
+        assert_eq!(item.map(|i| key_of(&i)).as_ref(), Some(key), "get must return the read model stored at the key");
+        assert!(ok(repo.contains_key(key), "contains_key"), "contains_key returned false for a stored key");
+    }
+
+    let missing = format!("{}-missing", keys.concat());
+    assert!(ok(repo.get(&missing), "get").is_none(), "get returned a read model for a key that isn't stored");
+    assert!(!ok(repo.contains_key(&missing), "contains_key"), "contains_key returned true for a key that isn't stored");
+
+    let mut paged = Vec::new();
+    let last_page = keys.len().div_ceil(2);
+    for page_num in 1..=last_page {
+        let page = ok(repo.get_paged(page_num, 2), "get_paged");
+        assert!(page.len() <= 2, "get_paged returned {} items for a page size of 2", page.len());
+        if let Some(total) = page.total {
+            assert_eq!(total, keys.len(), "get_paged must set the total to the number of stored read models");
+        }
+        paged.extend(page.items.iter().map(&key_of));
+    }
+    assert_same_keys(paged, keys, "get_paged");
+    let past_the_end = ok(repo.get_paged(last_page + 1, 2), "get_paged");
+    assert!(past_the_end.is_empty(), "get_paged must return an empty page past the last page");
+    assert!(!past_the_end.has_more, "get_paged must not report more items past the last page");
+
+    let everything = ok(repo.query(&QuerySpec::new()), "query");
+    assert_same_keys(everything.iter().map(&key_of).collect(), keys, "query with an empty spec");
+    let limited = ok(repo.query(&QuerySpec::new().limit(1)), "query");
+    assert!(limited.len() <= 1, "query returned {} read models for a limit of 1", limited.len());
+}
+
+/// event_repository_conformance checks that an `EventRepository` implementation follows the documented contract
+/// of each of its methods.  `repo` should be empty, and `new_event` should return an event with a new, unique id
+/// for the supplied aggregate id and version.
+///
+/// Events are stored for the aggregate ids `6f9619ff-8b86-d011-b42d-00cf4fc964ff` and
+/// `7c9e6679-7425-40de-944b-e07fc1f90ae7`, so point it at a test database.
+///
+/// # Panics
+///
+/// Panics with a message naming the method and the rule it broke if the repository doesn't follow the contract.
+pub fn event_repository_conformance<R, F>(mut repo: R, mut new_event: F)
+    where R: EventRepository,
+          F: FnMut(&String, u64) -> R::Events,
+{
+    let aggregate_id = "6f9619ff-8b86-d011-b42d-00cf4fc964ff".to_string();
+    let other_id = "7c9e6679-7425-40de-944b-e07fc1f90ae7".to_string();
+
+    assert!(!repo.contains_aggregate(&aggregate_id), "contains_aggregate returned true for an aggregate without events");
+    assert!(versions(repo.events_by_aggregate(&aggregate_id)).is_empty(), "events_by_aggregate returned events for an aggregate without events");
+    assert_eq!(repo.current_version(&aggregate_id), 0, "current_version must be 0 for an aggregate without events");
+
+    let events: Vec<R::Events> = (1..=5).map(|v| new_event(&aggregate_id, v)).collect();
+    for event in &events {
+        assert!(repo.insert(event).is_some_and(|e| e.id() == event.id()), "insert must return a new event");
+    }
+    for v in 1..=2 {
+        repo.insert(&new_event(&other_id, v));
+    }
+    assert!(repo.insert(&events[0]).is_none(), "insert must return None when the event id is already stored");
+
+    let event_id = events[0].id();
+    assert!(repo.get(&event_id).is_some_and(|e| e.id() == event_id), "get must return the event stored at the event id");
+    assert!(repo.contains_event(&event_id), "contains_event returned false for a stored event");
+    let missing = format!("{}-missing", event_id);
+    assert!(repo.get(&missing).is_none(), "get returned an event for an event id that isn't stored");
+    assert!(!repo.contains_event(&missing), "contains_event returned true for an event id that isn't stored");
+    assert!(repo.contains_aggregate(&aggregate_id), "contains_aggregate returned false for an aggregate with events");
+
+    let by_aggregate = repo.events_by_aggregate(&aggregate_id).unwrap_or_default();
+    assert!(by_aggregate.iter().all(|e| e.aggregate_id() == aggregate_id), "events_by_aggregate returned events of another aggregate");
+    assert_eq!(by_aggregate.len(), 5, "events_by_aggregate must return every event of the aggregate exactly once");
+
+    let since = |repo: &R, version| versions(repo.events_since_version(&aggregate_id, version));
+    assert_eq!(since(&repo, 0), vec![1, 2, 3, 4, 5], "events_since_version(0) must return every event in order");
+    assert_eq!(since(&repo, 2), vec![3, 4, 5], "events_since_version(2) must start from version 3");
+    assert!(since(&repo, 5).is_empty(), "events_since_version must return no events from the current version");
+
+    let num_since = |repo: &R, version, num| versions(repo.num_events_since_version(&aggregate_id, version, num));
+    assert_eq!(num_since(&repo, 0, 2), vec![1, 2], "num_events_since_version(0, 2) must return versions 1 and 2");
+    assert_eq!(num_since(&repo, 2, 2), vec![3, 4], "num_events_since_version(2, 2) must return versions 3 and 4");
+    assert_eq!(num_since(&repo, 4, 3), vec![5], "num_events_since_version must stop at the current version");
+    assert!(num_since(&repo, 5, 1).is_empty(), "num_events_since_version must return no events from the current version");
+    assert!(num_since(&repo, 1, 0).is_empty(), "num_events_since_version must return no events when asked for none");
+
+    assert_eq!(repo.current_version(&aggregate_id), 5, "current_version must be the highest stored version");
+    assert_eq!(repo.current_version(&other_id), 2, "current_version must only count the events of the aggregate");
+
+    let conflict = repo.append(&aggregate_id, 4, &[new_event(&aggregate_id, 5)]);
+    assert_eq!(
+        conflict,
+        Err(AppendError::VersionConflict(VersionConflictError { aggregate_id: aggregate_id.clone(), expected_version: 4, actual_version: 5 })),
+        "append must fail when the aggregate isn't at the expected version",
+    );
+    assert_eq!(repo.current_version(&aggregate_id), 5, "append must not insert events when it fails");
+
+    assert_eq!(repo.append(&aggregate_id, 5, &[new_event(&aggregate_id, 6)]), Ok(()), "append must succeed at the expected version");
+    assert_eq!(since(&repo, 5), vec![6], "append must insert the events");
+
+    let with_duplicate = [new_event(&aggregate_id, 7), events.into_iter().next().unwrap()];
+    assert_eq!(
+        repo.append(&aggregate_id, 6, &with_duplicate),
+        Err(AppendError::DuplicateEvent { event_id }),
+        "append must fail when an event id is already stored",
+    );
+    assert!(since(&repo, 6).is_empty(), "append must not insert any of the events when one is a duplicate");
+}
+
+// Unwraps the result of a repository method, or panics naming the method.
+fn ok<V, E: Display>(result: Result<V, E>, method: &str) -> V {
+    result.unwrap_or_else(|e| panic!("{} returned an error: {}", method, e))
+}
+
+// Returns the versions of the events, treating None as no events.
+fn versions<E: DomainEvent>(events: Option<Vec<E>>) -> Vec<u64> {
+    events.unwrap_or_default().iter().map(|e| e.version()).collect()
+}
+
+// Asserts that the returned keys are exactly the expected keys, in any order and without duplicates.
+fn assert_same_keys(mut returned: Vec<String>, expected: &[String], method: &str) {
+    let mut expected = expected.to_vec();
+    returned.sort();
+    expected.sort();
+    assert_eq!(returned, expected, "{} must return every stored item exactly once", method);
+}
+
+// Describes how the actual events differ from the expected events, or returns None if they're equal.
+fn diff_events<E: Debug + PartialEq>(expected: &[E], actual: &[E]) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    let mut diff = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => {
+                let _ = writeln!(diff, "  [{}] ok:         {:?}", i, a);
+            },
+            (Some(e), Some(a)) => {
+                let _ = writeln!(diff, "  [{}] expected:   {:?}", i, e);
+                let _ = writeln!(diff, "      actual:     {:?}", a);
+            },
+            (Some(e), None) => {
+                let _ = writeln!(diff, "  [{}] missing:    {:?}", i, e);
+            },
+            (None, Some(a)) => {
+                let _ = writeln!(diff, "  [{}] unexpected: {:?}", i, a);
+            },
+            (None, None) => (),
+        }
+    }
+
+    Some(diff)
+}
@@ -0,0 +1,241 @@
+use serde::{Serialize, Deserialize};
+use std::cell::Cell;
+use std::convert::{Infallible, TryFrom};
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Timestamp is a point in time in UTC, with millisecond precision.  It's stored as the number of milliseconds
+/// since the unix epoch, which is also how it's serialized.
+///
+/// Timestamps convert to and from `SystemTime`, and to and from chrono's `DateTime<Utc>` with the `chrono`
+/// feature.  Both of those cover a smaller range than a timestamp, so converting into them is done with `TryFrom`.
+/// A bare `i64` converts as milliseconds since the unix epoch.  `Display` writes the timestamp in RFC 3339 format.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// use domain_patterns::time::Timestamp;
+///
+/// let occurred = Timestamp::from_millis(1_564_660_800_250);
+/// assert_eq!(occurred.to_string(), "2019-08-01T12:00:00.250Z");
+///
+/// let system_time = SystemTime::try_from(occurred).unwrap();
+/// assert_eq!(system_time, UNIX_EPOCH + Duration::from_millis(1_564_660_800_250));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// Creates a timestamp from milliseconds since the unix epoch.
+    pub fn from_millis(millis: i64) -> Timestamp {
+        Timestamp(millis)
+    }
+
+    /// Creates a timestamp from whole seconds since the unix epoch, or returns [`None`] if the time in milliseconds
+    /// doesn't fit in an `i64`.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    pub fn from_secs(secs: i64) -> Option<Timestamp> {
+        secs.checked_mul(1000).map(Timestamp)
+    }
+
+    /// Returns the timestamp as milliseconds since the unix epoch.
+    pub fn as_millis(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the timestamp as whole seconds since the unix epoch, rounded down.
+    pub fn as_secs(&self) -> i64 {
+        self.0.div_euclid(1000)
+    }
+
+    /// Returns the timestamp the supplied duration later, or the latest timestamp if that's beyond the range of a
+    /// timestamp.
+    pub fn saturating_add(&self, duration: Duration) -> Timestamp {
+        let millis = i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+        Timestamp(self.0.saturating_add(millis))
+    }
+}
+
+impl From<i64> for Timestamp {
+    fn from(millis: i64) -> Timestamp {
+        Timestamp(millis)
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(timestamp: Timestamp) -> i64 {
+        timestamp.0
+    }
+}
+
+/// Times further from the unix epoch than a timestamp can hold are clamped to the earliest or latest timestamp.
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Timestamp {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => Timestamp(i64::try_from(since_epoch.as_millis()).unwrap_or(i64::MAX)),
+            Err(before_epoch) => Timestamp(i64::try_from(before_epoch.duration().as_millis()).map_or(i64::MIN, |m| -m)),
+        }
+    }
+}
+
+impl TryFrom<Timestamp> for SystemTime {
+    type Error = TimestampRangeError;
+
+    fn try_from(timestamp: Timestamp) -> Result<SystemTime, TimestampRangeError> {
+        let offset = Duration::from_millis(timestamp.0.unsigned_abs());
+        let time = if timestamp.0 < 0 {
+            UNIX_EPOCH.checked_sub(offset)
+        } else {
+            UNIX_EPOCH.checked_add(offset)
+        };
+
+        time.ok_or(TimestampRangeError { timestamp })
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(time: chrono::DateTime<chrono::Utc>) -> Timestamp {
+        Timestamp(time.timestamp_millis())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Timestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = TimestampRangeError;
+
+    fn try_from(timestamp: Timestamp) -> Result<chrono::DateTime<chrono::Utc>, TimestampRangeError> {
+        chrono::DateTime::from_timestamp_millis(timestamp.0).ok_or(TimestampRangeError { timestamp })
+    }
+}
+
+/// TimestampRangeError communicates that a `Timestamp` is outside the range of the time type it was converted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampRangeError {
+    pub timestamp: Timestamp,
+}
+
+impl fmt::Display for TimestampRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "timestamp of {} milliseconds since the unix epoch is out of range", self.timestamp.0)
+    }
+}
+
+impl Error for TimestampRangeError {}
+
+// Lets infallible conversions, such as into `i64` or `Timestamp` itself, be used wherever a conversion that can
+// fail with `TimestampRangeError` is expected.
+impl From<Infallible> for TimestampRangeError {
+    fn from(never: Infallible) -> TimestampRangeError {
+        match never {}
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.0.div_euclid(86_400_000);
+        let millis_of_day = self.0.rem_euclid(86_400_000);
+
+        // Converts days since the epoch to a civil date, from Howard Hinnant's date algorithms.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            millis_of_day / 3_600_000,
+            millis_of_day / 60_000 % 60,
+            millis_of_day / 1000 % 60,
+            millis_of_day % 1000,
+        )
+    }
+}
+
+/// Clock is an abstraction over reading the current time.  Anything that needs to know what time it is should
+/// take a `Clock` rather than reading the system time directly, so tests can control time deterministically.
+pub trait Clock {
+    /// now returns the current time.
+    fn now(&self) -> Timestamp;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Rc<C> {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+/// SystemClock reads the current time from the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from(SystemTime::now())
+    }
+}
+
+/// FixedClock is a clock that stands still at a given time, and only moves when it's told to.  It's meant for
+/// tests.  Because it uses interior mutability, share it between the code under test and the test itself by
+/// wrapping it in an `Rc`, and advance it from the test.
+///
+/// # Example
+/// ```rust
+/// use std::rc::Rc;
+/// use std::time::Duration;
+/// use domain_patterns::time::{Clock, FixedClock, Timestamp};
+///
+/// let clock = Rc::new(FixedClock::new(Timestamp::from_millis(1_000)));
+/// let shared = clock.clone();
+///
+/// clock.advance(Duration::from_millis(500));
+/// assert_eq!(shared.now(), Timestamp::from_millis(1_500));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FixedClock {
+    now: Cell<Timestamp>,
+}
+
+impl FixedClock {
+    pub fn new(now: Timestamp) -> FixedClock {
+        FixedClock {
+            now: Cell::new(now),
+        }
+    }
+
+    /// Moves the clock forward by the supplied duration, stopping at the latest timestamp.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get().saturating_add(duration));
+    }
+
+    /// Sets the clock to the supplied time.
+    pub fn set(&self, now: Timestamp) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.now.get()
+    }
+}
@@ -0,0 +1,693 @@
+use crate::models::{FieldValidation, ValidationErrors, ValueObject};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::{Ordering, Reverse};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+// Compiles a regular expression once, the first time it is used.
+macro_rules! pattern {
+    ($regex:expr) => {{
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        PATTERN.get_or_init(|| Regex::new($regex).expect("invalid regular expression"))
+    }};
+}
+
+// Implements `TryFrom`, `Display`, `FieldValidation` and serde for a value object wrapping a single value in a
+// tuple struct, whose `ValueObject` impl fails with `ValidationErrors`.  The value is passed through
+// `Self::normalize` before it is validated and stored.
+macro_rules! value_object_impls {
+    ($name:ident, $inner:ty, $code:expr) => {
+        impl TryFrom<$inner> for $name {
+            type Error = ValidationErrors;
+
+            fn try_from(value: $inner) -> Result<Self, Self::Error> {
+                let value = Self::normalize(value);
+                Self::validate(&value)?;
+
+                Ok($name(value))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl FieldValidation<$inner> for $name {
+            fn code() -> &'static str {
+                $code
+            }
+
+            fn validate_field(path: &str, value: &$inner) -> Result<(), ValidationErrors> {
+                Self::validate(&Self::normalize(value.clone())).map_err(|e| e.nest(path))
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$inner>::deserialize(deserializer)?;
+                Self::try_from(value).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+// Checks a string against a regular expression, failing with the supplied code and message.
+fn check_pattern(value: &str, pattern: &Regex, code: &str, message: &str) -> Result<(), ValidationErrors> {
+    if !pattern.is_match(value) {
+        return Err(ValidationErrors::single("", code, message));
+    }
+
+    Ok(())
+}
+
+/// Email holds an email address.  Surrounding whitespace is trimmed, and the address must be at most 254
+/// characters long.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use domain_patterns::models::ValueObject;
+/// use domain_patterns::values::Email;
+///
+/// let email = Email::try_from(" test_email@email.com ".to_string()).unwrap();
+/// assert_eq!(email.value(), "test_email@email.com");
+///
+/// let errors = Email::try_from("not an email".to_string()).unwrap_err();
+/// assert_eq!(errors.errors[0].code, "email");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Email(String);
+
+impl Email {
+    fn normalize(value: String) -> String {
+        value.trim().to_string()
+    }
+}
+
+impl ValueObject<String> for Email {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        if value.len() > 254 {
+            return Err(ValidationErrors::single("", "email", "must be at most 254 characters long"));
+        }
+
+        let email_rx = pattern!(
+            r"^(?i)[a-z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?(?:\.[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?)*$"
+        );
+        check_pattern(value, email_rx, "email", "must be a valid email address")
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(Email, String, "email");
+
+/// NonEmptyString holds a string that contains something other than whitespace.  The string is stored as given.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NonEmptyString(String);
+
+impl NonEmptyString {
+    fn normalize(value: String) -> String {
+        value
+    }
+}
+
+impl ValueObject<String> for NonEmptyString {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        if value.trim().is_empty() {
+            return Err(ValidationErrors::single("", "non_empty", "must not be empty"));
+        }
+
+        Ok(())
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(NonEmptyString, String, "non_empty");
+
+/// BoundedString holds a string that is at least `MIN` and at most `MAX` characters long.  Characters are
+/// counted as unicode scalar values, not bytes.  Failures are reported with the codes `min_len` and `max_len`.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use domain_patterns::values::BoundedString;
+///
+/// type Title = BoundedString<1, 5>;
+///
+/// assert!(Title::try_from("héllo".to_string()).is_ok());
+/// assert_eq!(Title::try_from("".to_string()).unwrap_err().errors[0].code, "min_len");
+/// assert_eq!(Title::try_from("too long".to_string()).unwrap_err().errors[0].code, "max_len");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoundedString<const MIN: usize, const MAX: usize>(String);
+
+impl<const MIN: usize, const MAX: usize> ValueObject<String> for BoundedString<MIN, MAX> {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        let len = value.chars().count();
+        if len < MIN {
+            return Err(ValidationErrors::single("", "min_len", &format!("must be at least {} characters long", MIN)));
+        }
+        if len > MAX {
+            return Err(ValidationErrors::single("", "max_len", &format!("must be at most {} characters long", MAX)));
+        }
+
+        Ok(())
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> TryFrom<String> for BoundedString<MIN, MAX> {
+    type Error = ValidationErrors;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+
+        Ok(BoundedString(value))
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> fmt::Display for BoundedString<MIN, MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> FieldValidation<String> for BoundedString<MIN, MAX> {
+    fn code() -> &'static str {
+        "length"
+    }
+
+    fn validate_field(path: &str, value: &String) -> Result<(), ValidationErrors> {
+        Self::validate(value).map_err(|e| e.nest(path))
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> Serialize for BoundedString<MIN, MAX> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, const MIN: usize, const MAX: usize> Deserialize<'de> for BoundedString<MIN, MAX> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Url holds an absolute url with a scheme and a host, such as `https://example.com/path?query`.  Surrounding
+/// whitespace is trimmed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url(String);
+
+impl Url {
+    fn normalize(value: String) -> String {
+        value.trim().to_string()
+    }
+
+    /// Returns the scheme of the url, such as `https`.
+    pub fn scheme(&self) -> &str {
+        self.0.split("://").next().unwrap_or_default()
+    }
+}
+
+impl ValueObject<String> for Url {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        let url_rx = pattern!(r"^[a-zA-Z][a-zA-Z0-9+.-]*://[^\s/?#@]+(?:[/?#]\S*)?$");
+        check_pattern(value, url_rx, "url", "must be an absolute url")
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(Url, String, "url");
+
+/// PhoneNumber holds a phone number in E.164 format: a `+` followed by the country code and subscriber number,
+/// 15 digits at most.  Spaces, dashes, dots and parentheses are removed before the number is validated, so
+/// `+1 (555) 010-9999` is stored as `+15550109999`.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use domain_patterns::values::PhoneNumber;
+///
+/// let phone = PhoneNumber::try_from("+1 (555) 010-9999".to_string()).unwrap();
+/// assert_eq!(phone.to_string(), "+15550109999");
+///
+/// assert!(PhoneNumber::try_from("555 010 9999".to_string()).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    fn normalize(value: String) -> String {
+        value.chars().filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')')).collect()
+    }
+}
+
+impl ValueObject<String> for PhoneNumber {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        let phone_rx = pattern!(r"^\+[1-9][0-9]{1,14}$");
+        check_pattern(value, phone_rx, "phone_number", "must be a phone number in E.164 format")
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(PhoneNumber, String, "phone_number");
+
+/// CountryCode holds an ISO 3166-1 alpha-2 country code, such as `US`.  The code is uppercased before it is
+/// validated.  Only the format of the code is checked, not whether the code is currently assigned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CountryCode(String);
+
+impl CountryCode {
+    fn normalize(value: String) -> String {
+        value.trim().to_ascii_uppercase()
+    }
+}
+
+impl ValueObject<String> for CountryCode {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        check_pattern(value, pattern!(r"^[A-Z]{2}$"), "country_code", "must be a two letter country code")
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(CountryCode, String, "country_code");
+
+/// CurrencyCode holds an ISO 4217 currency code, such as `USD`.  The code is uppercased before it is validated.
+/// Only the format of the code is checked, not whether the code is currently assigned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CurrencyCode(String);
+
+impl CurrencyCode {
+    fn normalize(value: String) -> String {
+        value.trim().to_ascii_uppercase()
+    }
+
+    /// Returns the number of decimal places of the currency's minor unit, as listed in ISO 4217.  For example
+    /// `USD` has 2, `JPY` has 0 and `KWD` has 3.  Currencies that aren't listed are assumed to have 2.
+    pub fn minor_units(&self) -> u32 {
+        match self.0.as_str() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "UYI" | "VND"
+            | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            "CLF" | "UYW" => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl ValueObject<String> for CurrencyCode {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &String) -> Result<(), ValidationErrors> {
+        check_pattern(value, pattern!(r"^[A-Z]{3}$"), "currency_code", "must be a three letter currency code")
+    }
+
+    fn value(&self) -> String {
+        self.0.clone()
+    }
+}
+
+value_object_impls!(CurrencyCode, String, "currency_code");
+
+/// Percentage holds a percentage from 0 to 100 inclusive.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use domain_patterns::values::Percentage;
+///
+/// let discount = Percentage::try_from(12.5).unwrap();
+/// assert_eq!(discount.as_fraction(), 0.125);
+///
+/// assert!(Percentage::try_from(101.0).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Percentage(f64);
+
+impl Percentage {
+    fn normalize(value: f64) -> f64 {
+        value
+    }
+
+    /// Returns the percentage as a fraction from 0 to 1.
+    pub fn as_fraction(&self) -> f64 {
+        self.0 / 100.0
+    }
+}
+
+impl ValueObject<f64> for Percentage {
+    type ValueError = ValidationErrors;
+
+    fn validate(value: &f64) -> Result<(), ValidationErrors> {
+        if !(0.0..=100.0).contains(value) {
+            return Err(ValidationErrors::single("", "percentage", "must be between 0 and 100"));
+        }
+
+        Ok(())
+    }
+
+    fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+value_object_impls!(Percentage, f64, "percentage");
+
+/// RoundingMode chooses how an amount that falls between two minor units is rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Rounds to the nearest minor unit, and away from zero when exactly halfway.
+    HalfUp,
+    /// Rounds to the nearest minor unit, and to the even one when exactly halfway.  Also known as bankers rounding.
+    HalfEven,
+    /// Rounds towards zero.
+    Down,
+    /// Rounds away from zero.
+    Up,
+    /// Rounds towards negative infinity.
+    Floor,
+    /// Rounds towards positive infinity.
+    Ceiling,
+}
+
+impl RoundingMode {
+    // Divides with the rounding mode applied to the result.  The denominator must not be zero.
+    fn divide(self, numerator: i128, denominator: i128) -> i128 {
+        let (numerator, denominator) = if denominator < 0 { (-numerator, -denominator) } else { (numerator, denominator) };
+        let floor = numerator.div_euclid(denominator);
+        let remainder = numerator.rem_euclid(denominator);
+        if remainder == 0 {
+            return floor;
+        }
+
+        let ceiling = floor + 1;
+        match self {
+            RoundingMode::Floor => floor,
+            RoundingMode::Ceiling => ceiling,
+            RoundingMode::Down => if numerator < 0 { ceiling } else { floor },
+            RoundingMode::Up => if numerator < 0 { floor } else { ceiling },
+            RoundingMode::HalfUp | RoundingMode::HalfEven => match (2 * remainder).cmp(&denominator) {
+                Ordering::Less => floor,
+                Ordering::Greater => ceiling,
+                Ordering::Equal if self == RoundingMode::HalfEven => if floor % 2 == 0 { floor } else { ceiling },
+                Ordering::Equal => if numerator < 0 { floor } else { ceiling },
+            },
+        }
+    }
+}
+
+/// MoneyError communicates why an operation on `Money` failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoneyError {
+    /// The amounts are in different currencies.
+    CurrencyMismatch { expected: CurrencyCode, found: CurrencyCode },
+    /// The result does not fit in the minor unit representation.
+    Overflow,
+    /// The amount could not be allocated, because there were no ratios, or they summed to zero.
+    InvalidRatios,
+    /// A decimal amount could not be parsed.
+    InvalidAmount(String),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { expected, found } => {
+                write!(f, "expected an amount in {}, but found an amount in {}", expected, found)
+            },
+            MoneyError::Overflow => write!(f, "the amount is too large"),
+            MoneyError::InvalidRatios => write!(f, "ratios must not be empty or sum to zero"),
+            MoneyError::InvalidAmount(amount) => write!(f, "{} is not a valid decimal amount", amount),
+        }
+    }
+}
+
+impl Error for MoneyError {}
+
+/// Money holds an amount of money in a currency.  The amount is stored as a whole number of the currency's minor
+/// unit, such as cents for `USD`, so arithmetic never loses precision.
+///
+/// Arithmetic is checked: it fails with `MoneyError::CurrencyMismatch` when amounts are in different currencies,
+/// and with `MoneyError::Overflow` instead of wrapping.  Amounts that don't fall on a minor unit, such as the result
+/// of applying an interest rate, are rounded with an explicit `RoundingMode`.  Amounts in different currencies
+/// are not comparable, so `partial_cmp` returns [`None`] for them.
+///
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use domain_patterns::values::{CurrencyCode, Money, RoundingMode};
+///
+/// let usd = CurrencyCode::try_from("USD".to_string()).unwrap();
+/// let price = Money::from_decimal("10.00", usd.clone(), RoundingMode::HalfEven).unwrap();
+///
+/// // Splitting three ways keeps every cent.
+/// let shares = price.split(3).unwrap();
+/// assert_eq!(shares.iter().map(|s| s.to_string()).collect::<Vec<_>>(), vec!["3.34 USD", "3.33 USD", "3.33 USD"]);
+///
+/// let tax = price.mul_ratio(825, 10_000, RoundingMode::HalfUp).unwrap();
+/// assert_eq!(price.checked_add(&tax).unwrap().to_string(), "10.83 USD");
+///
+/// let eur = CurrencyCode::try_from("EUR".to_string()).unwrap();
+/// assert!(price.checked_add(&Money::new(100, eur)).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    amount: i64,
+    currency: CurrencyCode,
+}
+
+impl Money {
+    /// Creates an amount from a whole number of the currency's minor unit, so `Money::new(1050, usd)` is $10.50.
+    pub fn new(minor_units: i64, currency: CurrencyCode) -> Money {
+        Money {
+            amount: minor_units,
+            currency,
+        }
+    }
+
+    /// Creates a zero amount in the currency.
+    pub fn zero(currency: CurrencyCode) -> Money {
+        Money::new(0, currency)
+    }
+
+    /// Parses a decimal amount in major units, such as `"-12.345"`, rounding it to the currency's minor unit.
+    pub fn from_decimal(amount: &str, currency: CurrencyCode, mode: RoundingMode) -> Result<Money, MoneyError> {
+        let invalid = || MoneyError::InvalidAmount(amount.to_string());
+
+        let (negative, digits) = match amount.trim() {
+            trimmed if trimmed.starts_with('-') => (true, &trimmed[1..]),
+            trimmed => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = match digits.find('.') {
+            Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+            None => (digits, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let scale = 10i128.checked_pow(fraction.len() as u32).ok_or(MoneyError::Overflow)?;
+        let mut numerator: i128 = format!("{}{}", whole, fraction).parse().map_err(|_| MoneyError::Overflow)?;
+        if negative {
+            numerator = -numerator;
+        }
+        let numerator = numerator.checked_mul(10i128.pow(currency.minor_units())).ok_or(MoneyError::Overflow)?;
+
+        let minor_units = i64::try_from(mode.divide(numerator, scale)).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::new(minor_units, currency))
+    }
+
+    /// Returns the amount as a whole number of the currency's minor unit.
+    pub fn minor_units(&self) -> i64 {
+        self.amount
+    }
+
+    /// Returns the currency of the amount.
+    pub fn currency(&self) -> &CurrencyCode {
+        &self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    // Fails unless the other amount is in the same currency.
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch {
+                expected: self.currency.clone(),
+                found: other.currency.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adds an amount in the same currency.
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency.clone()))
+    }
+
+    /// Subtracts an amount in the same currency.
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency.clone()))
+    }
+
+    /// Multiplies the amount by a whole number.
+    pub fn checked_mul(&self, factor: i64) -> Result<Money, MoneyError> {
+        let amount = self.amount.checked_mul(factor).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency.clone()))
+    }
+
+    /// Multiplies the amount by `numerator / denominator`, rounding the result to the currency's minor unit.  This
+    /// covers percentages, rates and exchange rates given as exact fractions.
+    pub fn mul_ratio(&self, numerator: i64, denominator: i64, mode: RoundingMode) -> Result<Money, MoneyError> {
+        if denominator == 0 {
+            return Err(MoneyError::InvalidRatios);
+        }
+
+        let product = self.amount as i128 * numerator as i128;
+        let amount = i64::try_from(mode.divide(product, denominator as i128)).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency.clone()))
+    }
+
+    /// Allocates the amount into parts proportional to the ratios, without losing any minor units.  The remainder
+    /// left after dividing is handed out one minor unit at a time to the parts with the largest fractional share,
+    /// earlier parts first on a tie, so the parts always add up to the original amount.  A part with a ratio of
+    /// zero always gets nothing.
+    pub fn allocate(&self, ratios: &[u64]) -> Result<Vec<Money>, MoneyError> {
+        let total: u128 = ratios.iter().map(|r| *r as u128).sum();
+        if total == 0 {
+            return Err(MoneyError::InvalidRatios);
+        }
+
+        let amount = self.amount as i128;
+        let mut parts: Vec<i128> = ratios.iter()
+            .map(|ratio| RoundingMode::Down.divide(amount * *ratio as i128, total as i128))
+            .collect();
+
+        // The fractional shares add up to the remainder and are each below one unit, so there are always enough
+        // parts with a non-zero share to hand the remainder out to.
+        let remainder = amount - parts.iter().sum::<i128>();
+        let fraction = |i: &usize| (amount * ratios[*i] as i128 % total as i128).abs();
+        let mut order: Vec<usize> = (0..ratios.len()).filter(|i| fraction(i) > 0).collect();
+        order.sort_by_key(|i| Reverse(fraction(i)));
+        for i in order.into_iter().take(remainder.unsigned_abs() as usize) {
+            parts[i] += remainder.signum();
+        }
+
+        Ok(parts.into_iter().map(|part| Money::new(part as i64, self.currency.clone())).collect())
+    }
+
+    /// Splits the amount into `parts` equal parts, handing out the remainder as `allocate` does.
+    pub fn split(&self, parts: usize) -> Result<Vec<Money>, MoneyError> {
+        self.allocate(&vec![1; parts])
+    }
+}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Money) -> Option<Ordering> {
+        if self.currency != other.currency {
+            return None;
+        }
+
+        Some(self.amount.cmp(&other.amount))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minor_units = self.currency.minor_units();
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let magnitude = self.amount.unsigned_abs();
+        if minor_units == 0 {
+            return write!(f, "{}{} {}", sign, magnitude, self.currency);
+        }
+
+        let scale = 10u64.pow(minor_units);
+        write!(
+            f,
+            "{}{}.{:0width$} {}",
+            sign,
+            magnitude / scale,
+            magnitude % scale,
+            self.currency,
+            width = minor_units as usize,
+        )
+    }
+}
+
+impl TryFrom<(i64, CurrencyCode)> for Money {
+    type Error = ValidationErrors;
+
+    fn try_from(value: (i64, CurrencyCode)) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+
+        Ok(Money::new(value.0, value.1))
+    }
+}
+
+impl ValueObject<(i64, CurrencyCode)> for Money {
+    type ValueError = ValidationErrors;
+
+    /// Every amount in minor units is valid, since the currency code was validated when it was constructed.
+    fn validate(_value: &(i64, CurrencyCode)) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+
+    fn value(&self) -> (i64, CurrencyCode) {
+        (self.amount, self.currency.clone())
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use domain_patterns::models::Entity;
-use domain_patterns::collections::{Repository, EventRepository};
+use domain_patterns::collections::{Repository, EventRepository, Page};
 use std::{fmt, error};
 use crate::common::{NaiveUser, UserEventRecord, UserEvents, Error};
 
@@ -42,27 +42,14 @@ impl Repository<NaiveUser> for MockUserRepository {
         Ok(result)
     }
 
-    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Option<Vec<NaiveUser>>, Error> {
+    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Page<NaiveUser>, Error> {
         let entire_collection: Vec<NaiveUser> = self.data
             .iter()
             .map(|(_, u)| {
                 u.clone()
             }).collect();
 
-        let result = if (page_num - 1) * page_size > entire_collection.len() {
-            None
-        } else {
-            let start = (page_num - 1) * page_size;
-            let end = if start + page_size > entire_collection.len() {
-                entire_collection.len()
-            } else {
-                start + page_size
-            };
-
-            Some(entire_collection[start..end].to_vec())
-        };
-
-        Ok(result)
+        Ok(Page::from_slice(&entire_collection, page_num, page_size))
     }
 
     fn update(&mut self, entity: &NaiveUser) -> Result<Option<String>, Error> {
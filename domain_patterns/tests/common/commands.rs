@@ -1,24 +1,42 @@
-use domain_patterns::command::{Command, Handles};
+use domain_patterns::command::{Command, Handles, IdempotentCommand, AggregateCommand};
 use domain_patterns::message::Message;
 use std::any::Any;
 use std::collections::HashMap;
 use domain_patterns::collections::Repository;
 use domain_patterns::models::Entity;
 use uuid::Uuid;
-use crate::common::{MockUserRepository, NaiveUser, Error};
+use crate::common::{MockUserRepository, NaiveUser, Email, Error};
 use crate::common::errors::Error::NotFound;
 
 #[derive(Command)]
 pub struct CreateUserCommand {
     pub id: Uuid,
+    #[validate(non_empty)]
     pub first_name: String,
+    #[validate(non_empty)]
     pub last_name: String,
+    #[validate(value_object = Email)]
     pub email: String,
 }
 
+// The user id doubles as the command id, since retrying creation of the same user is exactly the
+// duplicate we want to catch.
+impl IdempotentCommand for CreateUserCommand {
+    fn command_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+impl AggregateCommand for CreateUserCommand {
+    fn aggregate_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
 #[derive(Command)]
 pub struct ChangeEmailCommand {
     pub id: Uuid,
+    #[validate(non_empty, max_len = 255, value_object = Email)]
     pub email: String,
 }
 
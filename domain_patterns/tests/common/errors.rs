@@ -1,4 +1,6 @@
 use snafu::{Snafu, ResultExt, Backtrace, ErrorCompat, ensure};
+use domain_patterns::models::ValidationErrors;
+use domain_patterns::collections::AppendError;
 use std::fmt;
 use std::result;
 
@@ -12,4 +14,28 @@ pub enum Error {
 
     #[snafu(display("invalid email address"))]
     EmailError,
+
+    #[snafu(display("must be between 1 and 50 characters"))]
+    NameError,
+
+    #[snafu(display("that already exists"))]
+    AlreadyExists,
+
+    #[snafu(display("{}", source))]
+    InvalidCommand { source: ValidationErrors },
+
+    #[snafu(display("{}", source))]
+    AppendFailed { source: AppendError },
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(source: ValidationErrors) -> Self {
+        Error::InvalidCommand { source }
+    }
+}
+
+impl From<AppendError> for Error {
+    fn from(source: AppendError) -> Self {
+        Error::AppendFailed { source }
+    }
 }
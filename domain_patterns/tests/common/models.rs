@@ -2,10 +2,11 @@ use domain_patterns::models::{ValueObject, AggregateRoot, Entity};
 use regex::Regex;
 use std::convert::TryFrom;
 use uuid::Uuid;
-use crate::common::{UserEvents, UserCreatedEvent, Error};
+use crate::common::{UserEvents, UserCreatedEvent, FirstNameUpdatedEvent, Error};
 use crate::common::errors::Error::EmailError;
 
 #[derive(ValueSetup)]
+#[value_object(error = Error)]
 pub struct Email {
     pub value: String,
 }
@@ -86,3 +87,15 @@ pub fn create_test_user(user_id: &Uuid) -> NaiveUser {
         "test_email@email.com".to_string(),
     ).unwrap()
 }
+
+// Not every test crate that includes `common` publishes events.
+#[allow(dead_code)]
+pub fn create_first_name_updated_event(aggregate_id: &String, version: u64) -> UserEvents {
+    UserEvents::FirstNameUpdated(FirstNameUpdatedEvent {
+        id: Uuid::new_v4(),
+        aggregate_id: aggregate_id.clone(),
+        first_name: "new_name".to_string(),
+        version,
+        occurred: 120984128912,
+    })
+}
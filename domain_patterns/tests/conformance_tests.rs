@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::collections::*;
+use domain_patterns::models::Entity;
+use domain_patterns::spec::{FieldValue, Fields};
+use domain_patterns::testing::*;
+pub mod common;
+use common::*;
+use uuid::Uuid;
+
+// Wraps a repository, but forgets to check for duplicate keys on insert.
+pub struct OverwritingUserRepository {
+    inner: MockUserRepository,
+}
+
+impl Repository<NaiveUser> for OverwritingUserRepository {
+    type Error = Error;
+
+    fn insert(&mut self, entity: &NaiveUser) -> Result<Option<String>> {
+        self.inner.remove(&entity.id())?;
+        self.inner.insert(entity)
+    }
+
+    fn get(&mut self, key: &String) -> Result<Option<NaiveUser>> {
+        self.inner.get(key)
+    }
+
+    fn get_paged(&mut self, page_num: usize, page_size: usize) -> Result<Page<NaiveUser>> {
+        self.inner.get_paged(page_num, page_size)
+    }
+
+    fn update(&mut self, entity: &NaiveUser) -> Result<Option<String>> {
+        self.inner.update(entity)
+    }
+
+    fn remove(&mut self, key: &String) -> Result<Option<String>> {
+        self.inner.remove(key)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UserDto {
+    pub id: String,
+    pub email: String,
+}
+
+impl Fields for UserDto {
+    fn field(&self, name: &str) -> Option<FieldValue> {
+        match name {
+            "id" => Some(self.id.clone().into()),
+            "email" => Some(self.email.clone().into()),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_repository_conformance() {
+    repository_conformance(MockUserRepository::new(), || create_test_user(&Uuid::new_v4()));
+}
+
+#[test]
+#[allow(unused)]
+#[should_panic(expected = "insert must return None when the key is already stored")]
+fn test_repository_conformance_catches_overwriting_insert() {
+    let repo = OverwritingUserRepository { inner: MockUserRepository::new() };
+    repository_conformance(repo, || create_test_user(&Uuid::new_v4()));
+}
+
+#[test]
+#[allow(unused)]
+fn test_read_repository_conformance() {
+    let mut repo = InMemoryReadRepository::new();
+    let keys: Vec<String> = (1..=5).map(|i| i.to_string()).collect();
+    for key in &keys {
+        repo.insert(key, &UserDto { id: key.clone(), email: format!("user{}@example.com", key) });
+    }
+
+    read_repository_conformance(repo, &keys, |dto: &UserDto| dto.id.clone());
+}
+
+#[test]
+#[allow(unused)]
+fn test_event_repository_conformance() {
+    event_repository_conformance(UserEventRepository::new(), create_first_name_updated_event);
+}
@@ -0,0 +1,134 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::collections::*;
+use domain_patterns::command::{DeciderHandler, Handles};
+use domain_patterns::event::DomainEvent;
+use domain_patterns::models::Decider;
+pub mod common;
+use common::*;
+use chrono::Utc;
+use uuid::Uuid;
+
+// A minimal decider over the user events, which only knows whether the user exists and what
+// version it's at.
+struct UserDecider {
+    created: bool,
+    version: u64,
+}
+
+impl Decider for UserDecider {
+    type Command = CreateUserCommand;
+    type Events = UserEvents;
+    type Error = Error;
+
+    fn initial() -> Self {
+        UserDecider {
+            created: false,
+            version: 0,
+        }
+    }
+
+    fn decide(&self, command: CreateUserCommand) -> Result<Vec<UserEvents>> {
+        if self.created {
+            return Err(Error::AlreadyExists);
+        }
+
+        Ok(vec![UserEvents::UserCreated(UserCreatedEvent {
+            aggregate_id: command.id.to_string(),
+            first_name: command.first_name,
+            last_name: command.last_name,
+            email: command.email,
+            version: self.version + 1,
+            id: Uuid::new_v4(),
+            occurred: Utc::now().timestamp(),
+        })])
+    }
+
+    fn evolve(self, event: &UserEvents) -> Self {
+        match event {
+            UserEvents::UserCreated(e) => UserDecider { created: true, version: e.version },
+            UserEvents::FirstNameUpdated(e) => UserDecider { version: e.version, ..self },
+        }
+    }
+}
+
+fn create_user_command(id: Uuid) -> CreateUserCommand {
+    CreateUserCommand {
+        id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "email@email.com".to_string(),
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_decider_appends_decided_events() {
+    let mut handler: DeciderHandler<UserDecider, _> = DeciderHandler::new(UserEventRepository::new());
+    let user_id = Uuid::new_v4();
+
+    let events = handler.handle(create_user_command(user_id)).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].version(), 1);
+    assert!(handler.repo().contains_event(&events[0].id()));
+
+    let (state, version) = handler.load(&user_id.to_string());
+    assert!(state.created);
+    assert_eq!(version, 1);
+}
+
+#[test]
+#[allow(unused)]
+fn test_decider_rejects_command_from_loaded_state() {
+    let mut handler: DeciderHandler<UserDecider, _> = DeciderHandler::new(UserEventRepository::new());
+    let user_id = Uuid::new_v4();
+
+    handler.handle(create_user_command(user_id)).unwrap();
+    let result = handler.handle(create_user_command(user_id));
+
+    assert!(matches!(result, Err(Error::AlreadyExists)));
+}
+
+#[test]
+#[allow(unused)]
+fn test_append_with_stale_version_conflicts() {
+    let user_id = Uuid::new_v4();
+    let test_user = common::create_test_user(&user_id);
+    let mut user_event_repo = UserEventRepository::new();
+
+    let mut first = UserCreatedEvent::new(&test_user);
+    first.version = 1;
+    user_event_repo.append(&user_id.to_string(), 0, &[UserEvents::UserCreated(first)]).unwrap();
+
+    let mut second = UserCreatedEvent::new(&test_user);
+    second.version = 1;
+    let conflict = user_event_repo.append(&user_id.to_string(), 0, &[UserEvents::UserCreated(second)]).unwrap_err();
+
+    match conflict {
+        AppendError::VersionConflict(conflict) => {
+            assert_eq!(conflict.expected_version, 0);
+            assert_eq!(conflict.actual_version, 1);
+        },
+        other => panic!("expected a version conflict, got {:?}", other),
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_append_with_stored_event_id_fails() {
+    let user_id = Uuid::new_v4();
+    let test_user = common::create_test_user(&user_id);
+    let mut user_event_repo = UserEventRepository::new();
+
+    let mut first = UserCreatedEvent::new(&test_user);
+    first.version = 1;
+    let event_id = first.id.to_string();
+    user_event_repo.append(&user_id.to_string(), 0, &[UserEvents::UserCreated(first.clone())]).unwrap();
+
+    first.version = 2;
+    let result = user_event_repo.append(&user_id.to_string(), 1, &[UserEvents::UserCreated(first)]);
+
+    assert_eq!(result, Err(AppendError::DuplicateEvent { event_id }));
+    assert_eq!(user_event_repo.current_version(&user_id.to_string()), 1);
+}
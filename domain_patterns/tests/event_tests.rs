@@ -9,7 +9,13 @@ pub mod common;
 use common::*;
 use uuid::Uuid;
 use crate::common::UserEvents::UserCreated;
-use domain_patterns::event::DomainEvent;
+use domain_patterns::event::{DomainEvent, EventBus, PublishPolicy};
+use domain_patterns::command::Handles;
+use domain_patterns::message::Message;
+use domain_patterns::models::{FixedIdGenerator, SequentialIdGenerator};
+use domain_patterns::time::{FixedClock, Timestamp};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[test]
 #[allow(unused)]
@@ -45,3 +51,148 @@ fn test_retrieve_event() {
 
     assert_eq!(unpacked_event.id, user_created_event.id);
 }
+
+#[derive(Debug, Clone, DomainEvent)]
+#[event(constructor, id = "event_id", aggregate_id = "user_id", occurred = "at", version = "revision")]
+pub struct EmailChangedEvent {
+    pub event_id: Uuid,
+    pub user_id: String,
+    pub email: String,
+    pub revision: u32,
+    pub at: i64,
+}
+
+#[test]
+#[allow(unused)]
+fn test_event_with_renamed_fields() {
+    let (ids, clock) = (SequentialIdGenerator::new(), FixedClock::new(Timestamp::from_millis(1_000)));
+    let event = EmailChangedEvent::new(&ids, &clock, "user-1".to_string(), "test@email.com".to_string(), 3).unwrap();
+
+    assert_eq!(event.id(), "00000000-0000-0000-0000-000000000001");
+    assert_eq!(event.aggregate_id(), "user-1");
+    assert_eq!(event.version(), 3);
+    assert_eq!(event.occurred().as_millis(), 1_000);
+}
+
+#[test]
+#[allow(unused)]
+fn test_constructor_returns_id_parse_errors() {
+    let (ids, clock) = (FixedIdGenerator::new("order-1"), FixedClock::new(Timestamp::from_millis(1_000)));
+    let result = EmailChangedEvent::new(&ids, &clock, "user-1".to_string(), "test@email.com".to_string(), 3);
+
+    assert!(result.is_err());
+}
+
+// Records every event it's handed into a shared log, so tests can check publish order.
+struct Recorder {
+    name: &'static str,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+impl Handles<UserEvents> for Recorder {
+    type Result = std::result::Result<(), String>;
+
+    fn handle(&mut self, msg: UserEvents) -> Self::Result {
+        self.log.borrow_mut().push(format!("{}:{}", self.name, msg.version()));
+        Ok(())
+    }
+}
+
+impl Handles<FirstNameUpdatedEvent> for Recorder {
+    type Result = std::result::Result<(), String>;
+
+    fn handle(&mut self, msg: FirstNameUpdatedEvent) -> Self::Result {
+        self.log.borrow_mut().push(format!("{}:{}", self.name, msg.first_name));
+        Ok(())
+    }
+}
+
+struct Failing(&'static str);
+
+impl Handles<UserEvents> for Failing {
+    type Result = std::result::Result<(), String>;
+
+    fn handle(&mut self, _msg: UserEvents) -> Self::Result {
+        Err(self.0.to_string())
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_bus_publishes_in_registration_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut bus: EventBus<UserEvents, String> = EventBus::new(PublishPolicy::StopOnFirstError);
+    bus.subscribe(Recorder { name: "all", log: log.clone() });
+    bus.subscribe_to::<FirstNameUpdatedEvent, _, _>(Recorder { name: "first_name", log: log.clone() });
+
+    let user_id = Uuid::new_v4();
+    let test_user = common::create_test_user(&user_id);
+    bus.publish_all(&[UserCreated(UserCreatedEvent::new(&test_user)), create_first_name_updated_event(&user_id.to_string(), 1)]).unwrap();
+
+    // The variant subscriber skips the UserCreated event.
+    assert_eq!(*log.borrow(), vec!["all:0", "all:1", "first_name:new_name"]);
+}
+
+#[test]
+#[allow(unused)]
+fn test_bus_stops_at_first_failure() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut bus: EventBus<UserEvents, String> = EventBus::new(PublishPolicy::StopOnFirstError);
+    bus.subscribe(Failing("first"));
+    bus.subscribe(Failing("second"));
+    bus.subscribe(Recorder { name: "all", log: log.clone() });
+
+    let errors = bus.publish(&create_first_name_updated_event(&Uuid::new_v4().to_string(), 1)).unwrap_err();
+
+    assert_eq!(errors, vec!["first".to_string()]);
+    assert!(log.borrow().is_empty());
+}
+
+#[test]
+#[allow(unused)]
+fn test_bus_continues_and_collects_failures() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut bus: EventBus<UserEvents, String> = EventBus::new(PublishPolicy::ContinueOnError);
+    bus.subscribe(Failing("first"));
+    bus.subscribe(Failing("second"));
+    bus.subscribe(Recorder { name: "all", log: log.clone() });
+
+    let errors = bus.publish(&create_first_name_updated_event(&Uuid::new_v4().to_string(), 1)).unwrap_err();
+
+    assert_eq!(errors, vec!["first".to_string(), "second".to_string()]);
+    assert_eq!(*log.borrow(), vec!["all:1"]);
+}
+
+// Both variants hold the same event type, so neither can be subscribed to on its own.
+#[derive(Clone, DomainEvents)]
+pub enum RenamedEvents {
+    FirstNameUpdated(FirstNameUpdatedEvent),
+    FirstNameCorrected(FirstNameUpdatedEvent),
+}
+
+#[derive(Clone, DomainEvents)]
+pub enum ExtendedEvents<E: DomainEvent> {
+    FirstNameUpdated(FirstNameUpdatedEvent),
+    Extension(E),
+}
+
+#[test]
+#[allow(unused)]
+fn test_events_enums_with_repeated_and_generic_variants() {
+    let event = match create_first_name_updated_event(&Uuid::new_v4().to_string(), 2) {
+        UserEvents::FirstNameUpdated(e) => e,
+        _ => unreachable!(),
+    };
+
+    let corrected = RenamedEvents::FirstNameCorrected(event.clone());
+    assert_eq!(corrected.version(), 2);
+
+    let extended: ExtendedEvents<UserCreatedEvent> = ExtendedEvents::FirstNameUpdated(event.clone());
+    assert_eq!(extended.id(), event.id.to_string());
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut bus: EventBus<ExtendedEvents<UserCreatedEvent>, String> = EventBus::new(PublishPolicy::StopOnFirstError);
+    bus.subscribe_to::<FirstNameUpdatedEvent, _, _>(Recorder { name: "first_name", log: log.clone() });
+    bus.publish(&extended).unwrap();
+    assert_eq!(*log.borrow(), vec!["first_name:new_name"]);
+}
@@ -0,0 +1,82 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::collections::Repository;
+use domain_patterns::models::*;
+pub mod common;
+use common::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub struct Order;
+
+#[test]
+#[allow(unused)]
+fn test_id_parses_and_canonicalizes() {
+    let uuid: Id<NaiveUser> = "6F9619FF-8B86-D011-B42D-00CF4FC964FF".parse().unwrap();
+    assert_eq!(uuid.as_str(), "6f9619ff-8b86-d011-b42d-00cf4fc964ff");
+    assert!(uuid.is_uuid());
+
+    let ulid: Id<Order> = "01arz3ndektsv4rrffq69g5fav".parse().unwrap();
+    assert_eq!(ulid.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    assert!(ulid.is_ulid());
+
+    // ULIDs can't start above 7, or use I, L, O or U.
+    assert!("81ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Id<Order>>().is_err());
+    assert!("01ARZ3NDEKTSV4RRFFQ69G5FAU".parse::<Id<Order>>().is_err());
+    assert_eq!("".parse::<Id<Order>>().unwrap_err().errors[0].code, "id");
+}
+
+#[test]
+#[allow(unused)]
+fn test_id_serializes_as_a_validated_string() {
+    let id: Id<Order> = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+    assert_eq!(serde_json::to_string(&id).unwrap(), r#""01ARZ3NDEKTSV4RRFFQ69G5FAV""#);
+    assert_eq!(serde_json::from_str::<Id<Order>>(r#""01ARZ3NDEKTSV4RRFFQ69G5FAV""#).unwrap(), id);
+
+    let error = serde_json::from_str::<Id<Order>>(r#""order-1""#).err().unwrap();
+    assert!(error.to_string().contains("must be a UUID or a ULID"));
+}
+
+#[test]
+#[allow(unused)]
+fn test_id_is_accepted_by_repositories() {
+    let user_id = Uuid::new_v4();
+    let mut repo = MockUserRepository::new();
+    repo.insert(&common::create_test_user(&user_id)).unwrap();
+
+    let id: Id<NaiveUser> = user_id.to_string().parse().unwrap();
+    assert!(repo.get(&id).unwrap().is_some());
+    assert!(repo.contains_key(&id).unwrap());
+
+    // Ids can be looked up by their string form in maps keyed by id.
+    let mut names: HashMap<Id<NaiveUser>, &str> = HashMap::new();
+    names.insert(id.clone(), "first_name");
+    assert_eq!(names.get(user_id.to_string().as_str()), Some(&"first_name"));
+}
+
+#[cfg(all(feature = "uuid", feature = "ulid"))]
+#[test]
+#[allow(unused)]
+fn test_id_generation() {
+    let uuid: Id<Order> = Id::new_v4();
+    assert!(uuid.is_uuid());
+    assert_eq!(uuid.to_string().parse::<Id<Order>>().unwrap(), uuid);
+
+    let ulid: Id<Order> = Id::new_ulid();
+    assert!(ulid.is_ulid());
+    assert_ne!(ulid, Id::new_ulid());
+}
+
+#[test]
+#[allow(unused)]
+fn test_id_generators_produce_parseable_ids() {
+    let sequential = SequentialIdGenerator::new();
+    let first: Id<Order> = sequential.next_id().parse().unwrap();
+    let second: Uuid = sequential.next_id().parse().unwrap();
+    assert_eq!(first.as_str(), "00000000-0000-0000-0000-000000000001");
+    assert_eq!(second.to_string(), "00000000-0000-0000-0000-000000000002");
+
+    let fixed = FixedIdGenerator::new("6f9619ff-8b86-d011-b42d-00cf4fc964ff");
+    assert_eq!(fixed.next_id(), fixed.next_id());
+}
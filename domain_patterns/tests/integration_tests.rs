@@ -8,7 +8,7 @@ use domain_patterns::collections::*;
 mod common;
 use common::*;
 use uuid::Uuid;
-use domain_patterns::command::{Command, Handles};
+use domain_patterns::command::{Command, Handles, IdempotentHandler, InMemoryDedupeStore, DedupeStore, CommandBus};
 
 #[test]
 #[allow(unused)]
@@ -88,7 +88,26 @@ fn test_get_paged() {
     assert!(user_repo.contains_key(&user_id2.to_string()).unwrap());
 
     let results = user_repo.get_paged(1, 2).unwrap();
-    assert_eq!(results.unwrap().len(), 2)
+    assert_eq!(results.len(), 2);
+    assert_eq!(results.total, Some(2));
+    assert!(!results.has_more);
+
+    // Past the last page there is simply nothing left, but the total is still known.
+    let results = user_repo.get_paged(2, 2).unwrap();
+    assert!(results.is_empty());
+    assert_eq!(results.total, Some(2));
+}
+
+#[test]
+#[allow(unused)]
+fn test_page_serializes_for_rest_responses() {
+    let page = Page::with_total(vec!["first".to_string()], 1, 1, 3);
+
+    let json = serde_json::to_string(&page).unwrap();
+    assert_eq!(json, r#"{"items":["first"],"page_info":{"page_num":1,"page_size":1},"total":3,"has_more":true}"#);
+
+    let roundtrip: Page<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(roundtrip, page);
 }
 
 #[test]
@@ -112,3 +131,96 @@ fn test_survey_command() {
 
     assert!(user_command_handler.contains_key(&new_id.to_string()))
 }
+
+#[test]
+#[allow(unused)]
+fn test_retried_command_returns_stored_result() {
+    let user_repo = MockUserRepository::new();
+    let user_command_handler = UserCommandsHandler::new(user_repo);
+    let mut handler = IdempotentHandler::new(user_command_handler, InMemoryDedupeStore::new());
+
+    let new_id = Uuid::new_v4();
+    let create_user_command = CreateUserCommand {
+        id: new_id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "email@email.com".to_string()
+    };
+    let first_result = handler.handle(create_user_command).unwrap();
+    assert!(handler.store().contains_command(&new_id.to_string()));
+
+    // The retry carries an invalid email, so it would fail if the inner handler ran again.
+    let retried_command = CreateUserCommand {
+        id: new_id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "not an email".to_string()
+    };
+    let retried_result = handler.handle(retried_command).unwrap();
+
+    assert_eq!(first_result, retried_result);
+}
+
+#[test]
+#[allow(unused)]
+fn test_failed_command_is_not_recorded() {
+    let user_repo = MockUserRepository::new();
+    let user_command_handler = UserCommandsHandler::new(user_repo);
+    let mut handler = IdempotentHandler::new(user_command_handler, InMemoryDedupeStore::new());
+
+    let new_id = Uuid::new_v4();
+    let bad_command = CreateUserCommand {
+        id: new_id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "not an email".to_string()
+    };
+    assert!(handler.handle(bad_command).is_err());
+    assert!(!handler.store().contains_command(&new_id.to_string()));
+
+    let good_command = CreateUserCommand {
+        id: new_id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "email@email.com".to_string()
+    };
+    assert!(handler.handle(good_command).is_ok());
+}
+
+#[test]
+#[allow(unused)]
+fn test_command_bus_rejects_invalid_command() {
+    let user_repo = MockUserRepository::new();
+    let mut bus = CommandBus::new(UserCommandsHandler::new(user_repo));
+
+    let create_user_command = CreateUserCommand {
+        id: Uuid::new_v4(),
+        first_name: "".to_string(),
+        last_name: "test_last".to_string(),
+        email: "not an email".to_string()
+    };
+
+    let errors = match bus.dispatch(create_user_command) {
+        Err(Error::InvalidCommand { source }) => source.errors,
+        _ => panic!("expected command to fail validation"),
+    };
+    let failed_fields: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(failed_fields, vec!["first_name", "email"]);
+}
+
+#[test]
+#[allow(unused)]
+fn test_command_bus_dispatches_valid_command() {
+    let user_repo = MockUserRepository::new();
+    let mut bus = CommandBus::new(UserCommandsHandler::new(user_repo));
+
+    let new_id = Uuid::new_v4();
+    let create_user_command = CreateUserCommand {
+        id: new_id,
+        first_name: "test_first".to_string(),
+        last_name: "test_last".to_string(),
+        email: "email@email.com".to_string()
+    };
+
+    assert_eq!(bus.dispatch(create_user_command).unwrap(), Some(new_id.to_string()));
+}
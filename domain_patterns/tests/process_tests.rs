@@ -0,0 +1,184 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::command::{Command, Handles};
+use domain_patterns::event::DomainEvent;
+use domain_patterns::message::Message;
+use domain_patterns::process::*;
+use domain_patterns::time::{Clock, FixedClock, Timestamp};
+pub mod common;
+use common::*;
+use std::rc::Rc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Command, Clone, Debug, PartialEq)]
+pub enum OnboardingCommands {
+    ProvisionAccount { user_id: String },
+    SendWelcomeEmail { user_id: String },
+    DeactivateUser { user_id: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OnboardingState {
+    Started,
+    AccountRequested,
+    NameConfirmed,
+}
+
+// Onboards a user once they're created, and considers onboarding complete once the user confirmed their name.
+pub struct OnboardingProcess;
+
+impl ProcessManager for OnboardingProcess {
+    type Events = UserEvents;
+    type Commands = OnboardingCommands;
+    type State = OnboardingState;
+    type Error = Error;
+
+    fn correlation_id(&self, event: &UserEvents) -> Option<String> {
+        Some(event.aggregate_id())
+    }
+
+    fn initial_state(&self) -> OnboardingState {
+        OnboardingState::Started
+    }
+
+    fn react(&self, state: &mut OnboardingState, event: &UserEvents) -> Result<Vec<OnboardingCommands>> {
+        match event {
+            UserEvents::UserCreated(e) => {
+                *state = OnboardingState::AccountRequested;
+                Ok(vec![
+                    OnboardingCommands::ProvisionAccount { user_id: e.aggregate_id.clone() },
+                    OnboardingCommands::SendWelcomeEmail { user_id: e.aggregate_id.clone() },
+                ])
+            },
+            UserEvents::FirstNameUpdated(_) => {
+                *state = OnboardingState::NameConfirmed;
+                Ok(vec![])
+            },
+        }
+    }
+
+    fn compensate(&self, _state: &OnboardingState, failed: &OnboardingCommands) -> Vec<OnboardingCommands> {
+        match failed {
+            OnboardingCommands::ProvisionAccount { user_id } => {
+                vec![OnboardingCommands::DeactivateUser { user_id: user_id.clone() }]
+            },
+            _ => vec![],
+        }
+    }
+
+    fn is_complete(&self, state: &OnboardingState) -> bool {
+        *state == OnboardingState::NameConfirmed
+    }
+}
+
+// Records every command, and fails account provisioning when told to.
+pub struct OnboardingCommandsHandler {
+    handled: Vec<OnboardingCommands>,
+    fail_provisioning: bool,
+}
+
+impl Handles<OnboardingCommands> for OnboardingCommandsHandler {
+    type Result = Result<()>;
+
+    fn handle(&mut self, msg: OnboardingCommands) -> Self::Result {
+        self.handled.push(msg.clone());
+        match msg {
+            OnboardingCommands::ProvisionAccount { .. } if self.fail_provisioning => Err(Error::NotFound),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn runner(fail_provisioning: bool) -> ProcessRunner<OnboardingProcess, InMemoryProcessStateStore<OnboardingState>, OnboardingCommandsHandler> {
+    let handler = OnboardingCommandsHandler {
+        handled: vec![],
+        fail_provisioning,
+    };
+    ProcessRunner::new(OnboardingProcess, InMemoryProcessStateStore::new(), handler)
+}
+
+#[test]
+#[allow(unused)]
+fn test_process_dispatches_commands_and_keeps_state() {
+    let user_id = Uuid::new_v4();
+    let test_user = common::create_test_user(&user_id);
+    let mut runner = runner(false);
+
+    runner.handle(UserEvents::UserCreated(UserCreatedEvent::new(&test_user))).unwrap();
+
+    assert_eq!(runner.commands().handled, vec![
+        OnboardingCommands::ProvisionAccount { user_id: user_id.to_string() },
+        OnboardingCommands::SendWelcomeEmail { user_id: user_id.to_string() },
+    ]);
+    assert_eq!(runner.state(&user_id.to_string()), Some(OnboardingState::AccountRequested));
+
+    // Completing the process removes its state.
+    runner.handle(create_first_name_updated_event(&user_id.to_string(), 1)).unwrap();
+    assert_eq!(runner.state(&user_id.to_string()), None);
+}
+
+#[test]
+#[allow(unused)]
+fn test_process_compensates_failed_step() {
+    let user_id = Uuid::new_v4();
+    let test_user = common::create_test_user(&user_id);
+    let mut runner = runner(true);
+
+    let result = runner.handle(UserEvents::UserCreated(UserCreatedEvent::new(&test_user)));
+
+    assert!(matches!(result, Err(Error::NotFound)));
+    // The welcome email is never sent, and the user is deactivated instead.
+    assert_eq!(runner.commands().handled, vec![
+        OnboardingCommands::ProvisionAccount { user_id: user_id.to_string() },
+        OnboardingCommands::DeactivateUser { user_id: user_id.to_string() },
+    ]);
+    // The state isn't advanced, so a redelivered event reacts from the start again.
+    assert_eq!(runner.state(&user_id.to_string()), None);
+}
+
+#[test]
+#[allow(unused)]
+fn test_scheduled_timeout_is_delivered_when_due() {
+    let clock = Rc::new(FixedClock::new(Timestamp::from_millis(0)));
+    let mut scheduler = Scheduler::new(InMemoryScheduleStore::new(), clock.clone());
+    let mut handler = OnboardingCommandsHandler {
+        handled: vec![],
+        fail_provisioning: false,
+    };
+    let thirty_minutes = Duration::from_secs(30 * 60);
+
+    scheduler.schedule_in("user-1-timeout", thirty_minutes, OnboardingCommands::DeactivateUser { user_id: "user-1".to_string() });
+    scheduler.schedule_in("user-2-timeout", thirty_minutes, OnboardingCommands::DeactivateUser { user_id: "user-2".to_string() });
+    // user-2 confirmed in time, so their timeout is cancelled.
+    assert!(scheduler.cancel("user-2-timeout").is_some());
+
+    clock.advance(thirty_minutes - Duration::from_millis(1));
+    assert!(scheduler.deliver_due(&mut handler).is_empty());
+
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(scheduler.deliver_due(&mut handler).len(), 1);
+    assert_eq!(handler.handled, vec![OnboardingCommands::DeactivateUser { user_id: "user-1".to_string() }]);
+
+    // Delivered messages are not delivered again.
+    assert!(scheduler.deliver_due(&mut handler).is_empty());
+}
+
+#[test]
+#[allow(unused)]
+fn test_scheduling_for_the_end_of_time_never_comes_due() {
+    let clock = Rc::new(FixedClock::new(Timestamp::from_millis(1_000)));
+    let mut scheduler = Scheduler::new(InMemoryScheduleStore::new(), clock.clone());
+    let mut handler = OnboardingCommandsHandler {
+        handled: vec![],
+        fail_provisioning: false,
+    };
+
+    let never = scheduler.schedule_in("never", Duration::MAX, OnboardingCommands::DeactivateUser { user_id: "user-1".to_string() });
+    assert_eq!(never.map(|scheduled| scheduled.due), Some(Timestamp::from_millis(i64::MAX)));
+
+    clock.advance(Duration::from_millis(i64::MAX as u64 - 2_000));
+    assert_eq!(clock.now(), Timestamp::from_millis(i64::MAX - 1_000));
+    assert!(scheduler.deliver_due(&mut handler).is_empty());
+}
@@ -0,0 +1,106 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::collections::*;
+use domain_patterns::event::{EventBus, PublishPolicy};
+use domain_patterns::query::*;
+use domain_patterns::time::{FixedClock, Timestamp};
+pub mod common;
+use common::*;
+use std::rc::Rc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Query)]
+pub struct FirstNameQuery {
+    pub user_id: String,
+}
+
+impl CacheableQuery for FirstNameQuery {
+    fn cache_key(&self) -> String {
+        self.user_id.clone()
+    }
+
+    fn aggregate_ids(&self) -> Vec<String> {
+        vec![self.user_id.clone()]
+    }
+}
+
+// Looks up first names, counting how many times it actually had to go to the repository.
+pub struct UserQueryHandler {
+    repo: MockUserRepository,
+    times_handled: usize,
+}
+
+impl HandlesQuery<FirstNameQuery> for UserQueryHandler {
+    type Result = Result<Option<String>>;
+
+    fn handle(&mut self, query: FirstNameQuery) -> Self::Result {
+        self.times_handled += 1;
+        let user = self.repo.get(&query.user_id)?;
+        Ok(user.map(|u| u.first_name().clone()))
+    }
+}
+
+fn dispatcher(clock: Rc<FixedClock>) -> (QueryDispatcher<UserQueryHandler, UserEvents, Rc<FixedClock>>, String) {
+    let user_id = Uuid::new_v4();
+    let mut repo = MockUserRepository::new();
+    repo.insert(&common::create_test_user(&user_id)).unwrap();
+
+    let handler = UserQueryHandler { repo, times_handled: 0 };
+    let mut dispatcher = QueryDispatcher::new(handler, clock, Duration::from_secs(60));
+    dispatcher.invalidate_on::<FirstNameUpdatedEvent>();
+
+    (dispatcher, user_id.to_string())
+}
+
+#[test]
+#[allow(unused)]
+fn test_query_results_are_cached_until_they_expire() {
+    let clock = Rc::new(FixedClock::new(Timestamp::from_millis(0)));
+    let (mut dispatcher, user_id) = dispatcher(clock.clone());
+
+    let first = dispatcher.dispatch(FirstNameQuery { user_id: user_id.clone() }).unwrap();
+    let second = dispatcher.dispatch(FirstNameQuery { user_id: user_id.clone() }).unwrap();
+    assert_eq!(first, Some("first_name".to_string()));
+    assert_eq!(first, second);
+    assert_eq!(dispatcher.num_cached(), 1);
+    assert_eq!(dispatcher.inner().times_handled, 1);
+
+    clock.advance(Duration::from_secs(60));
+    dispatcher.dispatch(FirstNameQuery { user_id: user_id.clone() }).unwrap();
+    assert_eq!(dispatcher.inner().times_handled, 2);
+}
+
+#[test]
+#[allow(unused)]
+fn test_published_event_invalidates_cached_results() {
+    let clock = Rc::new(FixedClock::new(Timestamp::from_millis(0)));
+    let (mut dispatcher, user_id) = dispatcher(clock);
+    let mut bus: EventBus<UserEvents, Error> = EventBus::new(PublishPolicy::StopOnFirstError);
+    bus.subscribe(dispatcher.invalidator());
+
+    dispatcher.dispatch(FirstNameQuery { user_id: user_id.clone() }).unwrap();
+    assert_eq!(dispatcher.num_cached(), 1);
+
+    // Events for other aggregates leave the cached result alone.
+    bus.publish(&create_first_name_updated_event(&Uuid::new_v4().to_string(), 1)).unwrap();
+    assert_eq!(dispatcher.num_cached(), 1);
+
+    bus.publish(&create_first_name_updated_event(&user_id, 1)).unwrap();
+    assert_eq!(dispatcher.num_cached(), 0);
+}
+
+#[test]
+#[allow(unused)]
+fn test_unconfigured_event_does_not_invalidate() {
+    let clock = Rc::new(FixedClock::new(Timestamp::from_millis(0)));
+    let (mut dispatcher, user_id) = dispatcher(clock);
+
+    dispatcher.dispatch(FirstNameQuery { user_id: user_id.clone() }).unwrap();
+
+    let user_id_uuid = Uuid::parse_str(&user_id).unwrap();
+    let created = UserEvents::UserCreated(UserCreatedEvent::new(&common::create_test_user(&user_id_uuid)));
+    assert_eq!(dispatcher.invalidate(&created), 0);
+    assert_eq!(dispatcher.num_cached(), 1);
+}
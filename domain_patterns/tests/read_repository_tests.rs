@@ -0,0 +1,155 @@
+use domain_patterns::collections::*;
+use domain_patterns::spec::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserDto {
+    pub id: String,
+    pub email: String,
+    pub age: i64,
+    pub plan: Option<String>,
+}
+
+impl Fields for UserDto {
+    fn field(&self, name: &str) -> Option<FieldValue> {
+        match name {
+            "id" => Some(self.id.clone().into()),
+            "email" => Some(self.email.clone().into()),
+            "age" => Some(self.age.into()),
+            "plan" => Some(self.plan.clone().into()),
+            _ => None,
+        }
+    }
+}
+
+fn user(id: &str, email: &str, age: i64, plan: Option<&str>) -> UserDto {
+    UserDto {
+        id: id.to_string(),
+        email: email.to_string(),
+        age,
+        plan: plan.map(|p| p.to_string()),
+    }
+}
+
+fn read_repo() -> InMemoryReadRepository<UserDto> {
+    let mut repo = InMemoryReadRepository::new();
+    for u in [
+        user("1", "alice@example.com", 31, Some("pro")),
+        user("2", "bob@example.com", 17, None),
+        user("3", "al_b@test.com", 45, Some("free")),
+        user("4", "carol@example.com", 31, Some("free")),
+    ] {
+        repo.insert(&u.id.clone(), &u);
+    }
+    repo
+}
+
+fn ids(users: Vec<UserDto>) -> Vec<String> {
+    users.into_iter().map(|u| u.id).collect()
+}
+
+#[test]
+#[allow(unused)]
+fn test_query_filters_sorts_and_limits() {
+    let mut repo = read_repo();
+
+    let spec = QuerySpec::new()
+        .range("age", 18..=45)
+        .sort_asc("age")
+        .sort_desc("email")
+        .limit(2);
+
+    // alice and carol are both 31, so they're ordered by email descending.
+    assert_eq!(ids(repo.query(&spec).unwrap()), vec!["4", "1"]);
+}
+
+#[test]
+#[allow(unused)]
+fn test_query_in_prefix_and_null() {
+    let mut repo = read_repo();
+
+    let on_plan = QuerySpec::new().is_in("plan", vec!["pro", "free"]).sort_asc("id");
+    assert_eq!(ids(repo.query(&on_plan).unwrap()), vec!["1", "3", "4"]);
+
+    let no_plan = QuerySpec::new().eq("plan", FieldValue::Null);
+    assert_eq!(ids(repo.query(&no_plan).unwrap()), vec!["2"]);
+
+    // Prefixes are matched literally, so `_` is not a wildcard.
+    let prefixed = QuerySpec::new().prefix("email", "al_");
+    assert_eq!(ids(repo.query(&prefixed).unwrap()), vec!["3"]);
+
+    let nothing = QuerySpec::new().is_in("plan", Vec::<String>::new());
+    assert!(repo.query(&nothing).unwrap().is_empty());
+}
+
+#[test]
+#[allow(unused)]
+fn test_query_spec_translates_to_sql() {
+    let spec = QuerySpec::new()
+        .eq("plan", "pro")
+        .range("age", 18..65)
+        .is_in("id", vec!["1", "2"])
+        .prefix("email", "100%_")
+        .eq("deleted_at", FieldValue::Null)
+        .sort_desc("age")
+        .limit(10);
+
+    let query = spec.to_sql("user_dtos");
+
+    assert_eq!(
+        query.sql,
+        "SELECT * FROM \"user_dtos\" WHERE \"plan\" = ? AND \"age\" >= ? AND \"age\" < ? AND \"id\" IN (?, ?) \
+         AND typeof(\"email\") = 'text' AND substr(\"email\", 1, length(?)) = ? AND \"deleted_at\" IS NULL ORDER BY \"age\" DESC LIMIT 10"
+    );
+    assert_eq!(query.params, vec![
+        FieldValue::Text("pro".to_string()),
+        FieldValue::Int(18),
+        FieldValue::Int(65),
+        FieldValue::Text("1".to_string()),
+        FieldValue::Text("2".to_string()),
+        FieldValue::Text("100%_".to_string()),
+        FieldValue::Text("100%_".to_string()),
+    ]);
+}
+
+// Runs the SQL for a spec against an in-memory SQLite table of the same users as `read_repo`.
+fn sqlite_ids(spec: &QuerySpec) -> Vec<String> {
+    let db = rusqlite::Connection::open_in_memory().unwrap();
+    db.execute("CREATE TABLE user_dtos (id TEXT, email TEXT, age INTEGER, plan TEXT)", []).unwrap();
+    for u in read_repo().query(&QuerySpec::new()).unwrap() {
+        db.execute("INSERT INTO user_dtos VALUES (?, ?, ?, ?)", rusqlite::params![u.id, u.email, u.age, u.plan]).unwrap();
+    }
+
+    let query = spec.to_sql("user_dtos");
+    let params: Vec<Box<dyn rusqlite::ToSql>> = query.params.into_iter()
+        .map(|param| -> Box<dyn rusqlite::ToSql> {
+            match param {
+                FieldValue::Text(text) => Box::new(text),
+                FieldValue::Int(int) => Box::new(int),
+                other => panic!("unexpected parameter {:?}", other),
+            }
+        })
+        .collect();
+    let mut statement = db.prepare(&query.sql).unwrap();
+    let rows = statement.query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0)).unwrap();
+    rows.map(|row| row.unwrap()).collect()
+}
+
+#[test]
+#[allow(unused)]
+fn test_prefix_is_case_sensitive_in_memory_and_in_sql() {
+    let mut repo = read_repo();
+
+    let cases = [
+        ("email", "AL", vec![]),
+        ("email", "al", vec!["1", "3"]),
+        ("email", "al_", vec!["3"]),
+        ("email", "Carol", vec![]),
+        // age is an integer column, which never matches a prefix.
+        ("age", "3", vec![]),
+    ];
+    for (field, prefix, expected) in cases {
+        let spec = QuerySpec::new().prefix(field, prefix).sort_asc("id");
+        assert_eq!(ids(repo.query(&spec).unwrap()), expected, "in memory, prefix {}", prefix);
+        assert_eq!(sqlite_ids(&spec), expected, "in SQLite, prefix {}", prefix);
+    }
+}
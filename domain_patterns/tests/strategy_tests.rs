@@ -0,0 +1,120 @@
+#![cfg(all(feature = "proptest", feature = "regex"))]
+
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::event::DomainEvent;
+use domain_patterns::message::Message;
+use domain_patterns::models::{Id, ValidationErrors, ValueObject};
+use domain_patterns::strategy::proptest::prelude::*;
+use domain_patterns::strategy::ValueStrategy;
+use domain_patterns::time::Timestamp;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+#[derive(ValueSetup, Debug)]
+#[value_object(trim, min_len = 3, max_len = 12, arbitrary)]
+pub struct Username(String);
+
+#[derive(ValueSetup, Debug)]
+#[value_object(range = 1..=100, arbitrary)]
+pub struct Quantity(u8);
+
+#[derive(ValueSetup, Debug)]
+#[value_object(regex = r"^[A-Z]{3}-[0-9]{2}$", arbitrary)]
+pub struct Sku(String);
+
+#[derive(ValueSetup, Debug)]
+#[value_object(min_len = 1, arbitrary = hex_colors)]
+pub struct HexColor(String);
+
+fn hex_colors() -> impl Strategy<Value = String> {
+    "#[0-9a-f]{6}"
+}
+
+#[derive(ValueSetup, Debug)]
+#[value_object(arbitrary)]
+pub enum Size {
+    #[value = "small"]
+    Small,
+    #[value = "large"]
+    Large,
+}
+
+#[derive(ValueSetup, Debug)]
+#[value_object(check = starts_before_end, arbitrary)]
+pub struct Period {
+    start: u32,
+    end: u32,
+}
+
+fn starts_before_end(period: &Period) -> Result<(), ValidationErrors> {
+    if period.start > period.end {
+        return Err(ValidationErrors::single("end", "before_start", "must not be before the start"));
+    }
+    Ok(())
+}
+
+pub struct Order;
+
+#[derive(Debug, Clone, DomainEvent)]
+#[event(arbitrary)]
+pub struct ItemAddedEvent {
+    pub id: Uuid,
+    pub aggregate_id: Id<Order>,
+    pub sku: Sku,
+    pub quantity: Quantity,
+    pub size: Size,
+    pub note: Option<String>,
+    pub version: u64,
+    pub occurred: Timestamp,
+}
+
+// An event numbered by a sequence rather than by UUIDs.
+#[derive(Debug, Clone, DomainEvent)]
+#[event(arbitrary)]
+pub struct ShipmentSentEvent {
+    pub id: u64,
+    pub aggregate_id: String,
+    pub version: u64,
+    pub occurred: i64,
+}
+
+proptest! {
+    #[test]
+    fn test_generated_values_pass_validation(username in any::<Username>(), quantity in any::<Quantity>(), sku in any::<Sku>()) {
+        prop_assert!(Username::validate(&username.value()).is_ok());
+        prop_assert!(Quantity::validate(&quantity.value()).is_ok());
+        prop_assert!(Sku::validate(&sku.value()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_inputs_fail_validation(username in Username::invalid(), quantity in Quantity::invalid(), sku in Sku::invalid()) {
+        prop_assert!(Username::try_from(username).is_err());
+        prop_assert!(Quantity::try_from(quantity).is_err());
+        prop_assert!(Sku::try_from(sku).is_err());
+    }
+
+    #[test]
+    fn test_custom_inputs_enums_and_composites(color in any::<HexColor>(), size in any::<Size>(), status in Size::invalid(), period in any::<Period>()) {
+        prop_assert!(color.value().starts_with('#'));
+        prop_assert!(Size::all_values().contains(&size.value().as_str()));
+        prop_assert!(status.parse::<Size>().is_err());
+        prop_assert!(period.start <= period.end);
+    }
+
+    #[test]
+    fn test_generated_events_are_well_formed(event in any::<ItemAddedEvent>()) {
+        prop_assert!(event.id().parse::<Id<Order>>().is_ok());
+        prop_assert!(event.aggregate_id().parse::<Uuid>().is_ok());
+        prop_assert!(event.version() >= 1);
+        prop_assert!(event.occurred().as_millis() >= 0);
+        prop_assert!(Quantity::validate(&event.quantity.value()).is_ok());
+    }
+
+    #[test]
+    fn test_generated_events_with_non_uuid_ids(event in any::<ShipmentSentEvent>()) {
+        prop_assert!(event.id().parse::<u64>().is_ok());
+        prop_assert!(event.aggregate_id().parse::<Uuid>().is_ok());
+    }
+}
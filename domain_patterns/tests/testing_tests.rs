@@ -0,0 +1,273 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::collections::{AppendError, EventRepository};
+use domain_patterns::command::{AggregateCommand, Command, DeciderHandler};
+use domain_patterns::event::DomainEvent;
+use domain_patterns::message::Message;
+use domain_patterns::models::{AggregateRoot, Applier, Decider, Entity, FixedIdGenerator, Id, IdGenerator, SequentialIdGenerator};
+use domain_patterns::testing::*;
+use domain_patterns::time::{FixedClock, Timestamp};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, DomainEvent)]
+#[event(constructor)]
+pub struct AccountOpenedEvent {
+    pub id: Uuid,
+    pub aggregate_id: String,
+    pub version: u64,
+    pub occurred: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, DomainEvent)]
+#[event(constructor)]
+pub struct MoneyDepositedEvent {
+    pub id: Uuid,
+    pub aggregate_id: String,
+    pub amount: u64,
+    pub version: u64,
+    pub occurred: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, DomainEvents)]
+pub enum AccountEvents {
+    AccountOpened(AccountOpenedEvent),
+    MoneyDeposited(MoneyDepositedEvent),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AccountError {
+    NotOpen,
+    LimitExceeded,
+    AppendFailed,
+}
+
+impl From<AppendError> for AccountError {
+    fn from(_: AppendError) -> AccountError {
+        AccountError::AppendFailed
+    }
+}
+
+#[derive(Entity)]
+pub struct Account {
+    id: Id<Account>,
+    version: u64,
+    open: bool,
+    balance: u64,
+}
+
+impl AggregateRoot for Account {
+    type Events = AccountEvents;
+    type Error = AccountError;
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Applier for Account {
+    type EventError = AccountError;
+
+    fn apply(&mut self, event: AccountEvents) -> Result<(), AccountError> {
+        match event {
+            AccountEvents::AccountOpened(e) => {
+                self.open = true;
+                self.version = e.version;
+            },
+            AccountEvents::MoneyDeposited(e) => {
+                if !self.open {
+                    return Err(AccountError::NotOpen);
+                }
+                self.balance += e.amount;
+                self.version = e.version;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl Account {
+    fn deposit(&mut self, ids: &SequentialIdGenerator, clock: &FixedClock, amount: u64) -> Result<Vec<AccountEvents>, AccountError> {
+        if !self.open {
+            return Err(AccountError::NotOpen);
+        }
+        if self.balance + amount > 100 {
+            return Err(AccountError::LimitExceeded);
+        }
+
+        let event = MoneyDepositedEvent::new(ids, clock, self.id(), amount, self.next_version()).unwrap();
+        Ok(vec![AccountEvents::MoneyDeposited(event)])
+    }
+}
+
+fn account() -> Account {
+    Account {
+        id: "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap(),
+        version: 0,
+        open: false,
+        balance: 0,
+    }
+}
+
+fn opened(ids: &SequentialIdGenerator, clock: &FixedClock) -> AccountEvents {
+    AccountEvents::AccountOpened(AccountOpenedEvent::new(ids, clock, account().id(), 1).unwrap())
+}
+
+#[test]
+#[allow(unused)]
+fn test_given_when_then_expect_events() {
+    let (ids, clock) = (SequentialIdGenerator::new(), FixedClock::new(Timestamp::from_millis(1_000)));
+    let given_events = vec![opened(&ids, &clock)];
+
+    // The expected events are built with a generator in the same position as the one the aggregate will use.
+    let expected_ids = SequentialIdGenerator::new();
+    expected_ids.next_id();
+    let expected = MoneyDepositedEvent::new(&expected_ids, &clock, account().id(), 40, 2).unwrap();
+
+    given_aggregate(account(), given_events)
+        .when(|account| account.deposit(&ids, &clock, 40))
+        .then_expect(vec![AccountEvents::MoneyDeposited(expected)]);
+}
+
+#[test]
+#[allow(unused)]
+fn test_given_when_then_error() {
+    let (ids, clock) = (SequentialIdGenerator::new(), FixedClock::new(Timestamp::from_millis(1_000)));
+
+    given_aggregate(account(), vec![])
+        .when(|account| account.deposit(&ids, &clock, 40))
+        .then_error(|e| *e == AccountError::NotOpen);
+
+    given_aggregate(account(), vec![opened(&ids, &clock)])
+        .when(|account| account.deposit(&ids, &clock, 101))
+        .then_error(|e| *e == AccountError::LimitExceeded);
+}
+
+#[test]
+#[allow(unused)]
+fn test_failed_expectation_describes_the_difference() {
+    let (ids, clock) = (SequentialIdGenerator::new(), FixedClock::new(Timestamp::from_millis(1_000)));
+    let given_events = vec![opened(&ids, &clock)];
+
+    let result = std::panic::catch_unwind(move || {
+        given_aggregate(account(), given_events)
+            .when(|account| account.deposit(&ids, &clock, 40))
+            .then_expect(vec![]);
+    });
+
+    let message = *result.err().unwrap().downcast::<String>().unwrap();
+    assert!(message.starts_with("the raised events did not match the expected events:\n  [0] unexpected: MoneyDeposited("));
+    assert!(message.contains("amount: 40"));
+}
+
+// An event store over a plain vector, for scenarios that run through a command handler.
+#[derive(Default)]
+pub struct AccountEventStore {
+    events: Vec<AccountEvents>,
+}
+
+impl EventRepository for AccountEventStore {
+    type Events = AccountEvents;
+
+    fn events_by_aggregate(&self, aggregate_id: &String) -> Option<Vec<AccountEvents>> {
+        let events: Vec<AccountEvents> = self.events.iter().filter(|e| e.aggregate_id() == *aggregate_id).cloned().collect();
+        if events.is_empty() { None } else { Some(events) }
+    }
+
+    fn events_since_version(&self, aggregate_id: &String, version: u64) -> Option<Vec<AccountEvents>> {
+        self.events_by_aggregate(aggregate_id).map(|events| events.into_iter().filter(|e| e.version() > version).collect())
+    }
+
+    fn num_events_since_version(&self, aggregate_id: &String, version: u64, num_events: u64) -> Option<Vec<AccountEvents>> {
+        self.events_since_version(aggregate_id, version).map(|events| events.into_iter().take(num_events as usize).collect())
+    }
+
+    fn get(&self, event_id: &String) -> Option<AccountEvents> {
+        self.events.iter().find(|e| e.id() == *event_id).cloned()
+    }
+
+    fn contains_aggregate(&self, aggregate_id: &String) -> bool {
+        self.events_by_aggregate(aggregate_id).is_some()
+    }
+
+    fn insert(&mut self, event: &AccountEvents) -> Option<AccountEvents> {
+        if self.contains_event(&event.id()) {
+            return None;
+        }
+        self.events.push(event.clone());
+        Some(event.clone())
+    }
+}
+
+pub struct DepositCommand {
+    pub account_id: String,
+    pub amount: u64,
+}
+
+impl Message for DepositCommand {}
+
+impl Command for DepositCommand {}
+
+impl AggregateCommand for DepositCommand {
+    fn aggregate_id(&self) -> String {
+        self.account_id.clone()
+    }
+}
+
+// The account as a decider, so deposits can be handled by a `DeciderHandler`.
+pub struct AccountDecider {
+    open: bool,
+    balance: u64,
+    version: u64,
+}
+
+const DEPOSIT_ID: &str = "6f9619ff-8b86-d011-b42d-00cf4fc964ff";
+
+impl Decider for AccountDecider {
+    type Command = DepositCommand;
+    type Events = AccountEvents;
+    type Error = AccountError;
+
+    fn initial() -> Self {
+        AccountDecider { open: false, balance: 0, version: 0 }
+    }
+
+    fn decide(&self, command: DepositCommand) -> Result<Vec<AccountEvents>, AccountError> {
+        if !self.open {
+            return Err(AccountError::NotOpen);
+        }
+        if self.balance + command.amount > 100 {
+            return Err(AccountError::LimitExceeded);
+        }
+
+        let (ids, clock) = (FixedIdGenerator::new(DEPOSIT_ID), FixedClock::new(Timestamp::from_millis(2_000)));
+        let event = MoneyDepositedEvent::new(&ids, &clock, command.account_id, command.amount, self.version + 1).unwrap();
+        Ok(vec![AccountEvents::MoneyDeposited(event)])
+    }
+
+    fn evolve(self, event: &AccountEvents) -> Self {
+        match event {
+            AccountEvents::AccountOpened(e) => AccountDecider { open: true, version: e.version, ..self },
+            AccountEvents::MoneyDeposited(e) => AccountDecider { balance: self.balance + e.amount, version: e.version, ..self },
+        }
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_given_events_when_command_is_handled() {
+    let (ids, clock) = (SequentialIdGenerator::new(), FixedClock::new(Timestamp::from_millis(1_000)));
+    let deposit = |amount| DepositCommand { account_id: account().id(), amount };
+
+    let expected = MoneyDepositedEvent::new(&FixedIdGenerator::new(DEPOSIT_ID), &FixedClock::new(Timestamp::from_millis(2_000)), account().id(), 40, 2).unwrap();
+    given_events(AccountEventStore::default(), vec![opened(&ids, &clock)])
+        .handled_by(DeciderHandler::<AccountDecider, _>::new)
+        .when(deposit(40))
+        .then_expect(vec![AccountEvents::MoneyDeposited(expected)]);
+
+    given_events(AccountEventStore::default(), vec![])
+        .handled_by(DeciderHandler::<AccountDecider, _>::new)
+        .when(deposit(40))
+        .then_error(|e| *e == AccountError::NotOpen);
+}
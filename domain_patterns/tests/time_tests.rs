@@ -0,0 +1,42 @@
+use domain_patterns::time::*;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+#[allow(unused)]
+fn test_timestamp_from_secs_checks_for_overflow() {
+    assert_eq!(Timestamp::from_secs(-90), Some(Timestamp::from_millis(-90_000)));
+    assert_eq!(Timestamp::from_secs(i64::MAX / 1000 + 1), None);
+}
+
+#[test]
+#[allow(unused)]
+fn test_timestamp_converts_to_and_from_system_time() {
+    let before_epoch = Timestamp::from_millis(-1_500);
+    let system_time = SystemTime::try_from(before_epoch).unwrap();
+    assert_eq!(system_time, UNIX_EPOCH - Duration::from_millis(1_500));
+    assert_eq!(Timestamp::from(system_time), before_epoch);
+
+    // Times too far from the epoch for a timestamp are clamped rather than wrapping around.
+    let far_future = UNIX_EPOCH + Duration::from_secs(u64::MAX / 2);
+    assert_eq!(Timestamp::from(far_future), Timestamp::from_millis(i64::MAX));
+}
+
+#[test]
+#[allow(unused)]
+fn test_timestamp_saturating_add_stops_at_the_latest_timestamp() {
+    let start = Timestamp::from_millis(1_000);
+    assert_eq!(start.saturating_add(Duration::from_millis(500)), Timestamp::from_millis(1_500));
+    assert_eq!(start.saturating_add(Duration::MAX), Timestamp::from_millis(i64::MAX));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+#[allow(unused)]
+fn test_timestamp_out_of_range_for_chrono() {
+    let latest = Timestamp::from_millis(i64::MAX);
+    let error = chrono::DateTime::<chrono::Utc>::try_from(latest).unwrap_err();
+
+    assert_eq!(error, TimestampRangeError { timestamp: latest });
+    assert_eq!(error.to_string(), "timestamp of 9223372036854775807 milliseconds since the unix epoch is out of range");
+}
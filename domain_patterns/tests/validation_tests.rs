@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate domain_derive;
+
+use domain_patterns::models::*;
+pub mod common;
+use common::*;
+
+#[derive(ValueSetup)]
+#[value_object(code = "invalid_name", error = Error)]
+pub struct Name {
+    pub value: String,
+}
+
+impl ValueObject<String> for Name {
+    type ValueError = Error;
+
+    fn validate(value: &String) -> Result<()> {
+        if value.is_empty() || value.len() > 50 {
+            return Err(Error::NameError);
+        }
+
+        Ok(())
+    }
+
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+pub struct Address {
+    pub street: Name,
+    pub city: Name,
+}
+
+impl Address {
+    pub fn new(street: String, city: String) -> std::result::Result<Address, ValidationErrors> {
+        let (street, city) = (
+            Name::try_from_field("street", street),
+            Name::try_from_field("city", city),
+        ).validate_all()?;
+
+        Ok(Address { street, city })
+    }
+}
+
+#[test]
+#[allow(unused)]
+fn test_validate_all_collects_every_failure() {
+    let errors = (
+        Name::try_from_field("first_name", "".to_string()),
+        Email::try_from_field("email", "not an email".to_string()),
+        Name::try_from_field("last_name", "last".to_string()),
+    ).validate_all().err().unwrap();
+
+    assert_eq!(errors.errors, vec![
+        ValidationError::new("first_name", "invalid_name", "must be between 1 and 50 characters"),
+        ValidationError::new("email", "invalid_email", "invalid email address"),
+    ]);
+}
+
+#[test]
+#[allow(unused)]
+fn test_validate_all_returns_every_value() {
+    let (first, email) = (
+        Name::try_from_field("first_name", "first".to_string()),
+        Email::try_from_field("email", "test_email@email.com".to_string()),
+    ).validate_all().ok().unwrap();
+
+    assert_eq!(first.value(), "first");
+    assert_eq!(email.value(), "test_email@email.com");
+}
+
+#[test]
+#[allow(unused)]
+fn test_nested_errors_are_reported_at_field_paths() {
+    let mut errors = ValidationErrors::new();
+    let name = errors.check(Name::try_from_field("name", "name".to_string()));
+    let address = errors.check(Address::new("".to_string(), "".to_string()).map_err(|e| e.nest("addresses[0]")));
+
+    assert!(name.is_some());
+    assert!(address.is_none());
+
+    let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["addresses[0].street", "addresses[0].city"]);
+    assert_eq!(
+        serde_json::to_string(&errors.for_path("addresses[0].city")).unwrap(),
+        r#"[{"path":"addresses[0].city","code":"invalid_name","message":"must be between 1 and 50 characters"}]"#
+    );
+}
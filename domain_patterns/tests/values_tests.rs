@@ -0,0 +1,144 @@
+#![cfg(feature = "values")]
+
+use domain_patterns::models::*;
+use domain_patterns::values::*;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+#[derive(Serialize, Deserialize)]
+pub struct Contact {
+    pub email: Email,
+    pub phone: PhoneNumber,
+    pub country: CountryCode,
+}
+
+#[test]
+#[allow(unused)]
+fn test_string_values_normalize_and_validate() {
+    assert_eq!(Email::try_from(" test_email@email.com ".to_string()).unwrap().value(), "test_email@email.com");
+    assert!(Email::try_from("test_email@".to_string()).is_err());
+
+    assert!(NonEmptyString::try_from(" a ".to_string()).is_ok());
+    assert!(NonEmptyString::try_from("   ".to_string()).is_err());
+
+    let url = Url::try_from("https://example.com/path?q=1".to_string()).unwrap();
+    assert_eq!(url.scheme(), "https");
+    assert!(Url::try_from("example.com".to_string()).is_err());
+    assert!(Url::try_from("https://exa mple.com".to_string()).is_err());
+
+    assert_eq!(PhoneNumber::try_from("+44 20 7946 0958".to_string()).unwrap().value(), "+442079460958");
+    assert!(PhoneNumber::try_from("+0123".to_string()).is_err());
+    assert!(PhoneNumber::try_from("+1234567890123456".to_string()).is_err());
+
+    assert_eq!(CountryCode::try_from("us".to_string()).unwrap().value(), "US");
+    assert!(CountryCode::try_from("USA".to_string()).is_err());
+
+    assert_eq!(CurrencyCode::try_from("eur".to_string()).unwrap().value(), "EUR");
+    assert!(CurrencyCode::try_from("E1R".to_string()).is_err());
+}
+
+#[test]
+#[allow(unused)]
+fn test_numeric_and_bounded_values() {
+    assert!(Percentage::try_from(0.0).is_ok());
+    assert!(Percentage::try_from(100.0).is_ok());
+    assert!(Percentage::try_from(-0.5).is_err());
+    assert!(Percentage::try_from(f64::NAN).is_err());
+
+    type Code = BoundedString<2, 4>;
+    assert!(Code::try_from("ab".to_string()).is_ok());
+    assert_eq!(Code::try_from("a".to_string()).unwrap_err().errors[0].code, "min_len");
+    assert_eq!(Code::try_from("abcde".to_string()).unwrap_err().errors[0].code, "max_len");
+}
+
+#[test]
+#[allow(unused)]
+fn test_values_report_every_field() {
+    let result = (
+        Email::try_from_field("email", "nope".to_string()),
+        PhoneNumber::try_from_field("phone", "+15550109999".to_string()),
+        CountryCode::try_from_field("country", "U".to_string()),
+    ).validate_all();
+
+    let errors = result.err().unwrap();
+    let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["email", "country"]);
+    assert_eq!(errors.for_path("country")[0].code, "country_code");
+}
+
+#[test]
+#[allow(unused)]
+fn test_values_validate_while_deserializing() {
+    let contact: Contact = serde_json::from_str(r#"{"email":"a@b.com","phone":"+1 555 010 9999","country":"ca"}"#).unwrap();
+    assert_eq!(serde_json::to_string(&contact).unwrap(), r#"{"email":"a@b.com","phone":"+15550109999","country":"CA"}"#);
+
+    let error = serde_json::from_str::<Contact>(r#"{"email":"a@b.com","phone":"5550109999","country":"CA"}"#).err().unwrap();
+    assert!(error.to_string().contains("must be a phone number in E.164 format"));
+}
+
+fn currency(code: &str) -> CurrencyCode {
+    CurrencyCode::try_from(code.to_string()).unwrap()
+}
+
+#[test]
+#[allow(unused)]
+fn test_money_arithmetic_is_checked() {
+    let ten = Money::new(1000, currency("USD"));
+    let five = Money::new(500, currency("USD"));
+
+    assert_eq!(ten.checked_sub(&five).unwrap().checked_sub(&ten).unwrap().to_string(), "-5.00 USD");
+    assert_eq!(five.checked_mul(3).unwrap(), Money::new(1500, currency("USD")));
+    assert!(ten > five);
+
+    let yen = Money::new(500, currency("JPY"));
+    assert_eq!(
+        ten.checked_add(&yen),
+        Err(MoneyError::CurrencyMismatch { expected: currency("USD"), found: currency("JPY") }),
+    );
+    assert_eq!(ten.partial_cmp(&yen), None);
+    assert_eq!(Money::new(i64::MAX, currency("USD")).checked_add(&five), Err(MoneyError::Overflow));
+}
+
+#[test]
+#[allow(unused)]
+fn test_money_rounding_modes() {
+    let parse = |amount: &str, mode| Money::from_decimal(amount, currency("USD"), mode).unwrap().minor_units();
+
+    assert_eq!(parse("2.345", RoundingMode::HalfUp), 235);
+    assert_eq!(parse("-2.345", RoundingMode::HalfUp), -235);
+    assert_eq!(parse("2.345", RoundingMode::HalfEven), 234);
+    assert_eq!(parse("2.355", RoundingMode::HalfEven), 236);
+    assert_eq!(parse("2.341", RoundingMode::Up), 235);
+    assert_eq!(parse("-2.349", RoundingMode::Down), -234);
+    assert_eq!(parse("-2.341", RoundingMode::Floor), -235);
+    assert_eq!(parse("-2.349", RoundingMode::Ceiling), -234);
+
+    assert_eq!(Money::from_decimal("1500", currency("JPY"), RoundingMode::HalfUp).unwrap().to_string(), "1500 JPY");
+    assert_eq!(Money::from_decimal(".5", currency("KWD"), RoundingMode::HalfUp).unwrap().to_string(), "0.500 KWD");
+    assert!(Money::from_decimal("1.2.3", currency("USD"), RoundingMode::HalfUp).is_err());
+    assert!(Money::from_decimal("-", currency("USD"), RoundingMode::HalfUp).is_err());
+}
+
+#[test]
+#[allow(unused)]
+fn test_money_allocation_keeps_every_minor_unit() {
+    let total = Money::new(-1001, currency("EUR"));
+
+    let parts = total.allocate(&[70, 20, 10]).unwrap();
+    let units: Vec<i64> = parts.iter().map(|p| p.minor_units()).collect();
+    assert_eq!(units, vec![-701, -200, -100]);
+
+    let shares = Money::new(100, currency("EUR")).split(3).unwrap();
+    let units: Vec<i64> = shares.iter().map(|p| p.minor_units()).collect();
+    assert_eq!(units, vec![34, 33, 33]);
+
+    // Zero ratios never receive part of the remainder, which goes to the largest fractional shares.
+    let cent = Money::new(1, currency("EUR"));
+    let units: Vec<i64> = cent.allocate(&[0, 1, 1]).unwrap().iter().map(|p| p.minor_units()).collect();
+    assert_eq!(units, vec![0, 1, 0]);
+    let units: Vec<i64> = Money::new(-5, currency("EUR")).allocate(&[1, 0, 3]).unwrap().iter().map(|p| p.minor_units()).collect();
+    assert_eq!(units, vec![-1, 0, -4]);
+
+    assert_eq!(total.allocate(&[0, 0]), Err(MoneyError::InvalidRatios));
+    assert_eq!(total.split(0), Err(MoneyError::InvalidRatios));
+}